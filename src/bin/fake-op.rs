@@ -0,0 +1,1451 @@
+//! A fake 1password-cli for tests, built with `--features fake-op`.
+//!
+//! It understands the subcommands this crate emits and prints what the real
+//! cli prints, but keeps its state in a local JSON vault file instead of a
+//! 1Password account. The vault file is `$FAKE_OP_VAULT`, or
+//! `fake-op-vault.json` in the working directory. When it does not exist a
+//! vault is seeded for the account shorthand `my`, whose master password is
+//! `$FAKE_OP_PASSWORD` (`fake-op` by default). Like the real cli, the session
+//! is read from `--session` or from `OP_SESSION_<shorthand>`. The account can
+//! be added again under another shorthand with the sign-in address
+//! `my.1password.com`, the email `wendy@example.com` and the secret key
+//! `$FAKE_OP_SECRET_KEY` (`A3-FAKEOP-ABCDEF-GHIJK-LMNOP-QRSTU-VWXYZ` by default).
+//!
+//! Point `OpCLI` at it with
+//! `OpCLI::new_with_executor("my", &pass, ProcessExecutor::new(path_to_fake_op))`.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_PASSWORD: &str = "fake-op";
+const SECRET_KEY: &str = "A3-FAKEOP-ABCDEF-GHIJK-LMNOP-QRSTU-VWXYZ";
+const DOCUMENT_TEMPLATE: &str = "006";
+//fake-op speaks the v1 grammar.
+const VERSION: &str = "1.12.4";
+const PERMISSIONS: [&str; 15] = [
+    "view_items",
+    "create_items",
+    "edit_items",
+    "archive_items",
+    "delete_items",
+    "view_and_copy_passwords",
+    "view_item_history",
+    "import_items",
+    "export_items",
+    "copy_and_share_items",
+    "print_items",
+    "manage_vault",
+    "allow_viewing",
+    "allow_editing",
+    "allow_managing",
+];
+//granted when `add` is not given `--permissions`.
+const DEFAULT_PERMISSIONS: [&str; 3] = ["view_items", "create_items", "edit_items"];
+
+#[derive(Serialize, Deserialize)]
+struct Vault {
+    shorthand: String,
+    password: String,
+    #[serde(default)]
+    sessions: Vec<String>,
+    account: Value,
+    #[serde(default)]
+    items: Vec<Value>,
+    #[serde(default)]
+    documents: Vec<Document>,
+    #[serde(default)]
+    users: Vec<Value>,
+    #[serde(default)]
+    vaults: Vec<Value>,
+    #[serde(default)]
+    groups: Vec<Value>,
+    #[serde(default)]
+    events: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Document {
+    #[serde(flatten)]
+    meta: Value,
+    //documents may be binary, so they are kept as bytes.
+    content: Vec<u8>,
+}
+
+type CmdResult = std::result::Result<Vec<u8>, String>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match fake_op(args) {
+        Ok(out) => {
+            let mut stdout = std::io::stdout();
+            if let Err(err) = stdout.write_all(&out).and_then(|_| stdout.flush()) {
+                eprintln!("[ERROR] {}", err);
+                process::exit(1)
+            }
+        }
+        Err(err) => {
+            eprintln!("[ERROR] {} {}", Utc::now().format("%Y/%m/%d %H:%M:%S"), err);
+            process::exit(1)
+        }
+    }
+}
+
+fn fake_op(args: Vec<String>) -> CmdResult {
+    let path = vault_path();
+    let _lock = VaultLock::acquire(&path)?;
+    let mut vault = load_vault(&path)?;
+    let (args, session) = split_session(args);
+    let session = session.or_else(|| std::env::var(format!("OP_SESSION_{}", vault.shorthand)).ok());
+    let out = match args.first().map(String::as_str) {
+        Some("--version") => format!("{}\n", VERSION).into_bytes(),
+        Some("signin") => signin(&mut vault, &args[1..])?,
+        Some(_) if !session.is_some_and(|s| vault.sessions.contains(&s)) => {
+            return Err("You are not currently signed in. Please run `op signin --help` for instructions. Invalid session token".to_string())
+        }
+        Some(_) => {
+            let out = run(&mut vault, &args)?;
+            record_event(&mut vault, &args);
+            out
+        }
+        None => return Err("no command given".to_string()),
+    };
+    save_vault(&path, &vault)?;
+    Ok(out)
+}
+
+fn run(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let cmd: Vec<&str> = args.iter().take(2).map(String::as_str).collect();
+    let rest = &args[2.min(args.len())..];
+    match cmd.as_slice() {
+        ["get", "account"] => Ok(to_json(&vault.account)),
+        ["get", "item"] => get_item(vault, rest),
+        ["get", "document"] => {
+            let doc = find_document(vault, positional(rest, 0)?)?;
+            let content = vault.documents[doc].content.clone();
+            match flag_value(rest, "--output") {
+                Some(path) => {
+                    fs::write(path, content).map_err(|e| format!("{}: {}", path, e))?;
+                    Ok(Vec::new())
+                }
+                None => Ok(content),
+            }
+        }
+        ["get", "totp"] => {
+            let item = &vault.items[find_item(vault, positional(rest, 0)?)?];
+            match item.get("totp").and_then(Value::as_str) {
+                Some(totp) => Ok(format!("{}\n", totp).into_bytes()),
+                None => Err("no one-time password field found on item".to_string()),
+            }
+        }
+        ["get", "user"] => Ok(to_json(
+            &vault.users[find_user(vault, positional(rest, 0)?)?],
+        )),
+        ["list", "items"] => {
+            let trash = rest.iter().any(|arg| arg == "--include-trash");
+            let archive = rest.iter().any(|arg| arg == "--include-archive");
+            Ok(to_json(
+                &vault
+                    .items
+                    .iter()
+                    .filter(|item| trash || !is_trashed(item))
+                    .filter(|item| archive || !is_archived(item))
+                    .map(strip_item)
+                    .collect::<Vec<_>>(),
+            ))
+        }
+        ["list", "documents"] => Ok(to_json(
+            &vault
+                .documents
+                .iter()
+                .map(|doc| strip_item(&doc.meta))
+                .collect::<Vec<_>>(),
+        )),
+        ["list", "users"] => list_users(vault, rest),
+        ["list", "events"] => list_events(vault, rest),
+        ["list", "templates"] => Ok(to_json(
+            &TEMPLATES
+                .iter()
+                .map(|(uuid, name)| json!({"uuid": uuid, "name": name}))
+                .collect::<Vec<_>>(),
+        )),
+        ["get", "template"] => get_template(positional(rest, 0)?),
+        //these take the user in place of a subcommand.
+        ["confirm", ..] => {
+            let users: Vec<usize> = if args.iter().any(|arg| arg == "--all") {
+                (0..vault.users.len())
+                    .filter(|&index| vault.users[index]["state"] == "P")
+                    .collect()
+            } else {
+                vec![find_user(vault, positional(&args[1..], 0)?)?]
+            };
+            for index in users {
+                vault.users[index]["state"] = json!("A");
+            }
+            Ok(Vec::new())
+        }
+        ["suspend", ..] => {
+            let index = find_user(vault, positional(&args[1..], 0)?)?;
+            if let Some(delay) = flag_value(args, "--deauthorize-devices-after") {
+                if !delay.ends_with(['s', 'm', 'h']) {
+                    return Err(format!("invalid duration \"{}\"", delay));
+                }
+            }
+            vault.users[index]["state"] = json!("S");
+            Ok(Vec::new())
+        }
+        ["reactivate", ..] => {
+            let index = find_user(vault, positional(&args[1..], 0)?)?;
+            if vault.users[index]["state"] != "S" {
+                return Err("the user isn't suspended".to_string());
+            }
+            vault.users[index]["state"] = json!("A");
+            Ok(Vec::new())
+        }
+        ["create", "user"] => {
+            let email = positional(rest, 0)?;
+            if find_user(vault, email).is_ok() {
+                return Err(format!("a user with the email {} already exists", email));
+            }
+            let name = positional(rest, 1)?;
+            let (first_name, last_name) = name.split_once(' ').unwrap_or((name, ""));
+            let now = timestamp();
+            let user = json!({
+                "uuid": new_uuid().to_uppercase(),
+                "createdAt": now,
+                "updatedAt": now,
+                "lastAuthAt": now,
+                "firstName": first_name,
+                "lastName": last_name,
+                "name": name,
+                "email": email,
+                "attrVersion": 1,
+                "keysetVersion": 1,
+                "language": flag_value(rest, "--language").unwrap_or("en"),
+                "accountKeyFormat": "A3",
+                "accountKeyUuid": "",
+                "combinedPermissions": 0,
+                "avatar": "",
+                "state": "P",
+                "type": "R",
+            });
+            let out = to_json(&json!({
+                "uuid": user["uuid"],
+                "email": email,
+                "name": name,
+                "state": "P",
+            }));
+            vault.users.push(user);
+            Ok(out)
+        }
+        ["edit", "user"] => {
+            let index = find_user(vault, positional(rest, 0)?)?;
+            let user = &mut vault.users[index];
+            if let Some(name) = flag_value(rest, "--name") {
+                user["name"] = json!(name);
+            }
+            if let Some(travel_mode) = flag_value(rest, "--travel-mode") {
+                user["travelMode"] = json!(travel_mode == "on");
+            }
+            let version = user["attrVersion"].as_u64().unwrap_or(1);
+            user["attrVersion"] = json!(version + 1);
+            user["updatedAt"] = json!(timestamp());
+            Ok(Vec::new())
+        }
+        ["delete", "user"] => {
+            let index = find_user(vault, positional(rest, 0)?)?;
+            let user = vault.users.remove(index);
+            for group in vault.groups.iter_mut() {
+                if let Some(members) = group["members"].as_array_mut() {
+                    members.retain(|member| member["uuid"] != user["uuid"]);
+                }
+            }
+            Ok(Vec::new())
+        }
+        ["get", "vault"] => {
+            let index = find_vault(vault, positional(rest, 0)?)?;
+            Ok(to_json(&vault_with_items(vault, index)))
+        }
+        ["list", "vaults"] => Ok(to_json(
+            &vault
+                .vaults
+                .iter()
+                .map(|v| json!({"uuid": v["uuid"], "name": v["name"]}))
+                .collect::<Vec<_>>(),
+        )),
+        ["create", "vault"] => create_vault(vault, rest),
+        ["edit", "vault"] => edit_vault(vault, rest),
+        ["delete", "vault"] => {
+            let index = find_vault(vault, positional(rest, 0)?)?;
+            vault.vaults.remove(index);
+            Ok(Vec::new())
+        }
+        ["get", "group"] => {
+            let index = find_group(vault, positional(rest, 0)?)?;
+            Ok(to_json(&strip_group(&vault.groups[index])))
+        }
+        ["list", "groups"] => list_groups(vault, rest),
+        ["create", "group"] => {
+            let now = timestamp();
+            vault.groups.push(json!({
+                "uuid": new_uuid(),
+                "name": positional(rest, 0)?,
+                "desc": flag_value(rest, "--description").unwrap_or(""),
+                "state": "A",
+                "type": "U",
+                "createdAt": now,
+                "updatedAt": now,
+                "members": [],
+            }));
+            Ok(to_json(&strip_group(vault.groups.last().unwrap())))
+        }
+        ["edit", "group"] => {
+            let index = find_group(vault, positional(rest, 0)?)?;
+            let group = &mut vault.groups[index];
+            if let Some(name) = flag_value(rest, "--name") {
+                group["name"] = json!(name);
+            }
+            if let Some(description) = flag_value(rest, "--description") {
+                group["desc"] = json!(description);
+            }
+            group["updatedAt"] = json!(timestamp());
+            Ok(Vec::new())
+        }
+        ["delete", "group"] => {
+            let index = find_group(vault, positional(rest, 0)?)?;
+            vault.groups.remove(index);
+            Ok(Vec::new())
+        }
+        //`add user` and `remove user` take a group or a vault.
+        ["add", "user"] if find_group(vault, positional(rest, 1)?).is_ok() => {
+            add_user_to_group(vault, rest)
+        }
+        ["add", "user"] => {
+            let user = vault.users[find_user(vault, positional(rest, 0)?)?]["uuid"].clone();
+            grant(vault, "users", user, rest)
+        }
+        ["add", "group"] => {
+            let group = vault.groups[find_group(vault, positional(rest, 0)?)?]["uuid"].clone();
+            grant(vault, "groups", group, rest)
+        }
+        ["remove", "user"] if find_group(vault, positional(rest, 1)?).is_err() => {
+            let user = vault.users[find_user(vault, positional(rest, 0)?)?]["uuid"].clone();
+            revoke(vault, "users", user, rest)
+        }
+        ["remove", "group"] => {
+            let group = vault.groups[find_group(vault, positional(rest, 0)?)?]["uuid"].clone();
+            revoke(vault, "groups", group, rest)
+        }
+        ["remove", "user"] => {
+            let user = vault.users[find_user(vault, positional(rest, 0)?)?]["uuid"].clone();
+            let index = find_group(vault, positional(rest, 1)?)?;
+            let members = vault.groups[index]["members"]
+                .as_array_mut()
+                .ok_or_else(|| "group has no members".to_string())?;
+            let len = members.len();
+            members.retain(|member| member["uuid"] != user);
+            if members.len() == len {
+                return Err(format!(
+                    "user {} is not a member of the group",
+                    user.as_str().unwrap_or_default()
+                ));
+            }
+            Ok(Vec::new())
+        }
+        ["create", "document"] => create_document(vault, rest),
+        ["create", "item"] => create_item(vault, rest),
+        ["edit", "item"] => edit_item(vault, rest),
+        ["edit", "document"] => edit_document(vault, rest),
+        //deleted items go to the trash, or the archive with `--archive`.
+        ["delete", "item"] => {
+            let index = find_item(vault, positional(rest, 0)?)?;
+            let item = &mut vault.items[index];
+            if rest.iter().any(|arg| arg == "--archive") {
+                item["archived"] = json!(true);
+            } else {
+                item["trashed"] = json!("Y");
+            }
+            item["updatedAt"] = json!(timestamp());
+            Ok(Vec::new())
+        }
+        ["delete", "trash"] => {
+            let index = find_vault(vault, positional(rest, 0)?)?;
+            let uuid = vault.vaults[index]["uuid"].clone();
+            vault
+                .items
+                .retain(|item| item["vaultUuid"] != uuid || !is_trashed(item));
+            Ok(Vec::new())
+        }
+        ["restore", "item"] => {
+            let index = find_item(vault, positional(rest, 0)?)?;
+            let item = &mut vault.items[index];
+            if !is_trashed(item) && !is_archived(item) {
+                return Err("item is not in the trash or the archive".to_string());
+            }
+            item["trashed"] = json!("N");
+            item["archived"] = json!(false);
+            item["updatedAt"] = json!(timestamp());
+            Ok(Vec::new())
+        }
+        ["delete", "document"] => {
+            let doc = find_document(vault, positional(rest, 0)?)?;
+            vault.documents.remove(doc);
+            Ok(Vec::new())
+        }
+        _ => Err(format!("unknown command \"{}\"", cmd.join(" "))),
+    }
+}
+
+fn signin(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| e.to_string())?;
+    //`signin <signinaddress> <emailaddress> --shorthand <shorthand>` adds the
+    //account first, it prompts for the secret key before the password.
+    let password = match positionals(args)[..] {
+        [address, email] => {
+            let (secret_key, password) = input.split_once('\n').unwrap_or((&input, ""));
+            let shorthand = flag_value(args, "--shorthand");
+            add_account(vault, address, email, secret_key.trim_end(), shorthand)?;
+            password
+        }
+        _ => &input,
+    };
+    let shorthand = match flag_value(args, "--shorthand") {
+        Some(shorthand) => shorthand,
+        None => positional(args, 0)?,
+    };
+    if shorthand != vault.shorthand {
+        return Err(format!(
+            "Account not found. Use `op signin <signinaddress> <emailaddress>` to add \"{}\"",
+            shorthand
+        ));
+    }
+    if password.trim_end_matches(['\r', '\n']) != vault.password {
+        return Err("401: Authentication required.".to_string());
+    }
+    let token = random_string(
+        43,
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+    );
+    vault.sessions.push(token.clone());
+    Ok(format!("{}\n", token).into_bytes())
+}
+
+//the account is known by the shorthand it was added with, which defaults
+//to the subdomain of the sign-in address.
+fn add_account(
+    vault: &mut Vault,
+    address: &str,
+    email: &str,
+    secret_key: &str,
+    shorthand: Option<&str>,
+) -> Result<(), String> {
+    let host = address.trim_start_matches("https://");
+    let domain = host.split('.').next().unwrap_or_default();
+    let secret = std::env::var("FAKE_OP_SECRET_KEY").unwrap_or_else(|_| SECRET_KEY.to_string());
+    if domain != vault.account["domain"]
+        || !vault.users.iter().any(|user| user["email"] == email)
+        || secret_key != secret
+    {
+        return Err("401: Authentication required.".to_string());
+    }
+    vault.shorthand = shorthand.unwrap_or(domain).to_string();
+    Ok(())
+}
+
+fn get_item(vault: &Vault, args: &[String]) -> CmdResult {
+    let key = positional(args, 0)?;
+    //documents are items too.
+    if find(vault.items.iter(), key).is_none() {
+        if let Some(doc) = find(vault.documents.iter().map(|doc| &doc.meta), key) {
+            return Ok(to_json(&document_item(vault, &vault.documents[doc?])));
+        }
+    }
+    let item = &vault.items[find_item(vault, key)?];
+    let fields = match flag_value(args, "--fields") {
+        Some(fields) => fields,
+        None => return Ok(to_json(&strip_totp(item))),
+    };
+    let mut out = serde_json::Map::new();
+    for field in fields.split(',') {
+        let value = match field {
+            "website" => item["overview"]["url"].clone(),
+            field => item["details"]["fields"]
+                .as_array()
+                .and_then(|fields| {
+                    fields
+                        .iter()
+                        .find(|f| f["designation"] == field || f["name"] == field)
+                })
+                .map(|f| f["value"].clone())
+                .unwrap_or(Value::Null),
+        };
+        out.insert(field.to_string(), value);
+    }
+    Ok(to_json(&out))
+}
+
+//the item of a document, as `get item` prints it.
+fn document_item(vault: &Vault, doc: &Document) -> Value {
+    let mut item = doc.meta.clone();
+    item["trashed"] = json!("N");
+    item["changerUuid"] = vault
+        .users
+        .first()
+        .map(|user| user["uuid"].clone())
+        .unwrap_or_default();
+    item["details"]["documentAttributes"]["size"] = json!(doc.content.len());
+    item
+}
+
+//`op edit document <document> <file>`, the contents are read from stdin
+//when the file is `-`.
+fn edit_document(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let index = find_document(vault, positional(args, 0)?)?;
+    let content = match positional(args, 1)? {
+        "-" => {
+            let mut content = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut content)
+                .map_err(|e| e.to_string())?;
+            content
+        }
+        path => fs::read(path).map_err(|e| format!("{}: {}", path, e))?,
+    };
+    let doc = &mut vault.documents[index];
+    doc.content = content;
+    if let Some(file_name) = flag_value(args, "--file-name") {
+        doc.meta["details"]["documentAttributes"]["fileName"] = json!(file_name);
+    }
+    if let Some(title) = flag_value(args, "--title") {
+        doc.meta["overview"]["title"] = json!(title);
+    }
+    let version = doc.meta["itemVersion"].as_u64().unwrap_or_default();
+    doc.meta["itemVersion"] = json!(version + 1);
+    doc.meta["updatedAt"] = json!(timestamp());
+    Ok(Vec::new())
+}
+
+fn create_document(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let path = PathBuf::from(positional(args, 0)?);
+    let content = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let title = flag_value(args, "--title")
+        .unwrap_or(&file_name)
+        .to_string();
+    let vault_uuid = target_vault(vault, args)?;
+    let now = timestamp();
+    let uuid = new_uuid();
+    vault.documents.push(Document {
+        meta: json!({
+            "uuid": uuid,
+            "templateUuid": DOCUMENT_TEMPLATE,
+            "createdAt": now,
+            "updatedAt": now,
+            "itemVersion": 1,
+            "vaultUuid": vault_uuid,
+            "details": {"documentAttributes": {"fileName": file_name}},
+            "overview": {"title": title},
+        }),
+        content,
+    });
+    Ok(to_json(&json!({
+        "uuid": uuid,
+        "createdAt": now,
+        "updatedAt": now,
+        "vaultUuid": vault_uuid,
+    })))
+}
+
+fn create_item(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let category = positional(args, 0)?;
+    let template_uuid = template_uuid(category)
+        .ok_or_else(|| format!("\"{}\" isn't a valid category", category))?;
+    //the details are read from the `--template` file, or decoded from the
+    //argument older versions took.
+    let details = match flag_value(args, "--template") {
+        Some(path) => fs::read(path).map_err(|e| format!("{}: {}", path, e))?,
+        None => URL_SAFE_NO_PAD
+            .decode(positional(args, 1)?)
+            .map_err(|_| "Unable to parse the item details".to_string())?,
+    };
+    let details: Value = serde_json::from_slice(&details)
+        .map_err(|_| "Unable to parse the item details".to_string())?;
+    let title = flag_value(args, "--title").unwrap_or("Untitled Item");
+    let vault_uuid = target_vault(vault, args)?;
+    let mut overview = json!({ "title": title });
+    if let Some(url) = flag_value(args, "--url") {
+        overview["url"] = json!(url);
+    }
+    if let Some(tags) = flag_value(args, "--tags") {
+        overview["tags"] = json!(tags.split(',').collect::<Vec<_>>());
+    }
+    let changer_uuid = vault.users.first().map(|user| user["uuid"].clone());
+    let now = timestamp();
+    let uuid = new_uuid();
+    vault.items.push(json!({
+        "uuid": uuid,
+        "templateUuid": template_uuid,
+        "trashed": "N",
+        "createdAt": now,
+        "updatedAt": now,
+        "changerUuid": changer_uuid,
+        "itemVersion": 1,
+        "vaultUuid": vault_uuid,
+        "details": details,
+        "overview": overview,
+    }));
+    Ok(to_json(&json!({
+        "uuid": uuid,
+        "createdAt": now,
+        "updatedAt": now,
+        "vaultUuid": vault_uuid,
+    })))
+}
+
+fn edit_item(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let index = find_item(vault, positional(args, 0)?)?;
+    let item = &mut vault.items[index];
+    for assignment in positionals(args).into_iter().skip(1) {
+        let (field, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("invalid assignment \"{}\"", assignment))?;
+        assign(item, field, value);
+    }
+    if let Some(flag) = args
+        .iter()
+        .find(|arg| arg.starts_with("--generate-password"))
+    {
+        let length = flag
+            .split(['=', ','])
+            .find_map(|part| part.parse().ok())
+            .unwrap_or(32);
+        let password = random_string(
+            length,
+            "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+        );
+        assign(item, "password", &password);
+    }
+    if let Some(title) = flag_value(args, "--title") {
+        item["overview"]["title"] = json!(title);
+    }
+    if let Some(url) = flag_value(args, "--url") {
+        item["overview"]["url"] = json!(url);
+    }
+    if let Some(tags) = flag_value(args, "--tags") {
+        item["overview"]["tags"] = json!(tags.split(',').collect::<Vec<_>>());
+    }
+    let version = item["itemVersion"].as_u64().unwrap_or(1);
+    item["itemVersion"] = json!(version + 1);
+    item["updatedAt"] = json!(timestamp());
+    Ok(Vec::new())
+}
+
+//apply `field=value` or `section.field[type]=value` to an item's details.
+fn assign(item: &mut Value, field: &str, value: &str) {
+    let (field, kind) = match field.split_once('[') {
+        Some((field, kind)) => (field, kind.trim_end_matches(']')),
+        None => (field, "text"),
+    };
+    let details = &mut item["details"];
+    match field.split_once('.') {
+        None => {
+            if !details["fields"].is_array() {
+                details["fields"] = json!([]);
+            }
+            let fields = details["fields"].as_array_mut().unwrap();
+            match fields
+                .iter_mut()
+                .find(|f| f["designation"] == field || f["name"] == field)
+            {
+                Some(f) => f["value"] = json!(value),
+                None => fields.push(json!({"name": field, "type": "T", "value": value})),
+            }
+        }
+        Some((section, field)) => {
+            if !details["sections"].is_array() {
+                details["sections"] = json!([]);
+            }
+            let sections = details["sections"].as_array_mut().unwrap();
+            let position = sections
+                .iter()
+                .position(|s| s["name"] == section || s["title"] == section);
+            let section = match position {
+                Some(position) => &mut sections[position],
+                None => {
+                    sections.push(json!({"name": section, "title": section, "fields": []}));
+                    sections.last_mut().unwrap()
+                }
+            };
+            if !section["fields"].is_array() {
+                section["fields"] = json!([]);
+            }
+            let fields = section["fields"].as_array_mut().unwrap();
+            match fields
+                .iter_mut()
+                .find(|f| f["n"] == field || f["t"] == field)
+            {
+                Some(f) => f["v"] = json!(value),
+                None => fields.push(json!({"k": kind, "n": field, "t": field, "v": value})),
+            }
+        }
+    }
+}
+
+//the templates items are created from, as (uuid, name).
+//Document items are made by `create document`, not from a template.
+const TEMPLATES: [(&str, &str); 21] = [
+    ("001", "Login"),
+    ("002", "Credit Card"),
+    ("003", "Secure Note"),
+    ("004", "Identity"),
+    ("005", "Password"),
+    ("006", "Document"),
+    ("100", "Software License"),
+    ("101", "Bank Account"),
+    ("102", "Database"),
+    ("103", "Driver License"),
+    ("104", "Outdoor License"),
+    ("105", "Membership"),
+    ("106", "Passport"),
+    ("107", "Reward Program"),
+    ("108", "Social Security Number"),
+    ("109", "Wireless Router"),
+    ("110", "Server"),
+    ("111", "Email Account"),
+    ("112", "API Credential"),
+    ("113", "Medical Record"),
+    ("114", "SSH Key"),
+];
+
+fn template_uuid(category: &str) -> Option<&'static str> {
+    TEMPLATES
+        .iter()
+        .find(|(uuid, name)| *name == category && *uuid != DOCUMENT_TEMPLATE)
+        .map(|(uuid, _)| *uuid)
+}
+
+fn get_template(key: &str) -> CmdResult {
+    let (uuid, _) = TEMPLATES
+        .iter()
+        .find(|(uuid, name)| *uuid == key || name.eq_ignore_ascii_case(key))
+        .ok_or_else(|| format!("\"{}\" doesn't seem to be a template", key))?;
+    let login = json!([
+        {"designation": "username", "name": "username", "type": "T", "value": ""},
+        {"designation": "password", "name": "password", "type": "P", "value": ""},
+    ]);
+    let password = json!([
+        {"designation": "password", "name": "password", "type": "P", "value": ""},
+    ]);
+    let details = match *uuid {
+        "001" => json!({"notesPlain": "", "fields": login, "sections": []}),
+        "005" => json!({"notesPlain": "", "fields": password, "sections": []}),
+        "003" => json!({"notesPlain": ""}),
+        _ => json!({
+            "notesPlain": "",
+            "sections": [{"name": "", "title": "", "fields": [
+                {"k": "string", "n": "name", "t": "name", "v": ""},
+            ]}],
+        }),
+    };
+    Ok(to_json(&details))
+}
+
+fn create_vault(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let name = positional(args, 0)?;
+    let allow_admins = flag_value(args, "--allow-admins-to-manage").unwrap_or("true");
+    if allow_admins != "true" && allow_admins != "false" {
+        return Err(format!(
+            "invalid value \"{}\" for --allow-admins-to-manage",
+            allow_admins
+        ));
+    }
+    let now = timestamp();
+    vault.vaults.push(json!({
+        "uuid": new_uuid(),
+        "name": name,
+        "desc": flag_value(args, "--description").unwrap_or(""),
+        "avatar": flag_value(args, "--icon").unwrap_or(""),
+        "type": "U",
+        "attrVersion": 1,
+        "contentVersion": 1,
+        "createdAt": now,
+        "updatedAt": now,
+    }));
+    Ok(to_json(&vault_with_items(vault, vault.vaults.len() - 1)))
+}
+
+fn edit_vault(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let index = find_vault(vault, positional(args, 0)?)?;
+    let edited = &mut vault.vaults[index];
+    if let Some(name) = flag_value(args, "--name") {
+        edited["name"] = json!(name);
+    }
+    if let Some(description) = flag_value(args, "--description") {
+        edited["desc"] = json!(description);
+    }
+    if let Some(icon) = flag_value(args, "--icon") {
+        edited["avatar"] = json!(icon);
+    }
+    if let Some(travel_mode) = flag_value(args, "--travel-mode") {
+        edited["travelMode"] = json!(travel_mode == "on");
+    }
+    let version = edited["attrVersion"].as_u64().unwrap_or(1);
+    edited["attrVersion"] = json!(version + 1);
+    edited["updatedAt"] = json!(timestamp());
+    Ok(Vec::new())
+}
+
+fn vault_with_items(vault: &Vault, index: usize) -> Value {
+    let mut found = vault.vaults[index].clone();
+    let uuid = &found["uuid"];
+    let items = vault
+        .items
+        .iter()
+        .filter(|item| &item["vaultUuid"] == uuid)
+        .count()
+        + vault
+            .documents
+            .iter()
+            .filter(|doc| &doc.meta["vaultUuid"] == uuid)
+            .count();
+    found["items"] = json!(items);
+    found
+}
+
+//the uuid of the vault given with `--vault`, the Private vault by default.
+fn target_vault(vault: &Vault, args: &[String]) -> Result<String, String> {
+    match flag_value(args, "--vault") {
+        Some(key) => {
+            let index = find_vault(vault, key)?;
+            Ok(vault.vaults[index]["uuid"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string())
+        }
+        None => Ok(vault.account["vaultUuid"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(new_uuid)),
+    }
+}
+
+fn find_vault(vault: &Vault, key: &str) -> Result<usize, String> {
+    vault
+        .vaults
+        .iter()
+        .position(|v| v["uuid"] == key || v["name"] == key)
+        .ok_or_else(|| {
+            format!(
+                "\"{}\" doesn't seem to be a vault in this account. Specify the vault with its UUID or name.",
+                key
+            )
+        })
+}
+
+//`op list events` prints a page of events, the newest first.
+//The page size is `$FAKE_OP_EVENT_PAGE_SIZE`, 100 by default like the real cli.
+fn list_events(vault: &Vault, args: &[String]) -> CmdResult {
+    let page_size = std::env::var("FAKE_OP_EVENT_PAGE_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(100);
+    let eid = match flag_value(args, "--eventid") {
+        Some(eid) => Some(
+            eid.parse::<u64>()
+                .map_err(|_| format!("invalid event id \"{}\"", eid))?,
+        ),
+        None => None,
+    };
+    let older = args.iter().any(|arg| arg == "--older");
+    let mut events: Vec<&Value> = vault
+        .events
+        .iter()
+        .filter(|event| {
+            let id = event["eid"].as_u64().unwrap_or_default();
+            match eid {
+                Some(eid) if older => id < eid,
+                Some(eid) => id > eid,
+                None => true,
+            }
+        })
+        .collect();
+    events.sort_by_key(|event| std::cmp::Reverse(event["eid"].as_u64()));
+    events.truncate(page_size);
+    Ok(to_json(&events))
+}
+
+//log what a command changed, like the real account's activity log.
+fn record_event(vault: &mut Vault, args: &[String]) {
+    let cmd: Vec<&str> = args.iter().take(2).map(String::as_str).collect();
+    let (action, object_type, rest) = match cmd.as_slice() {
+        ["add", "user"] | ["add", "group"] => ("grant", "access", &args[2..]),
+        ["remove", "user"] | ["remove", "group"] => ("revoke", "access", &args[2..]),
+        ["create", object] => ("create", *object, &args[2..]),
+        ["edit", object] => ("update", *object, &args[2..]),
+        ["delete", "item"] if args.iter().any(|arg| arg == "--archive") => {
+            ("archive", "item", &args[2..])
+        }
+        ["delete", object] => ("delete", *object, &args[2..]),
+        ["restore", object] => ("restore", *object, &args[2..]),
+        [action @ ("confirm" | "suspend" | "reactivate"), ..] => (*action, "user", &args[1..]),
+        _ => return,
+    };
+    let object = positional(rest, 0).unwrap_or_default().to_string();
+    push_event(vault, action, object_type, &object);
+}
+
+fn push_event(vault: &mut Vault, action: &str, object_type: &str, object: &str) {
+    let eid = vault
+        .events
+        .iter()
+        .filter_map(|event| event["eid"].as_u64())
+        .max()
+        .unwrap_or(1000)
+        + 1;
+    let actor = vault
+        .users
+        .first()
+        .map(|user| user["uuid"].clone())
+        .unwrap_or_default();
+    vault.events.push(json!({
+        "eid": eid,
+        "time": timestamp(),
+        "actorUuid": actor,
+        "action": action,
+        "objectType": object_type,
+        "objectUuid": object,
+        "auxInfo": "",
+    }));
+}
+
+fn list_users(vault: &Vault, args: &[String]) -> CmdResult {
+    let mut users: Vec<Value> = vault.users.iter().map(list_user).collect();
+    if let Some(key) = flag_value(args, "--group") {
+        let members = &vault.groups[find_group(vault, key)?]["members"];
+        users = users
+            .into_iter()
+            .filter_map(|mut user| {
+                let member = members
+                    .as_array()?
+                    .iter()
+                    .find(|member| member["uuid"] == user["uuid"])?;
+                user["role"] = member["role"].clone();
+                Some(user)
+            })
+            .collect();
+    }
+    if let Some(key) = flag_value(args, "--vault") {
+        let access = &vault.vaults[find_vault(vault, key)?];
+        users = users
+            .into_iter()
+            .filter_map(|mut user| {
+                user["permissions"] = json!(user_permissions(vault, access, &user["uuid"])?);
+                Some(user)
+            })
+            .collect();
+    }
+    Ok(to_json(&users))
+}
+
+//what a user may do in `access`, granted directly or through their groups.
+//None when the user can not access it at all.
+fn user_permissions(vault: &Vault, access: &Value, user: &Value) -> Option<Vec<String>> {
+    let mut permissions: Option<Vec<String>> = granted(access, "users", user);
+    for group in &vault.groups {
+        let is_member = group["members"]
+            .as_array()
+            .is_some_and(|members| members.iter().any(|m| &m["uuid"] == user));
+        if !is_member {
+            continue;
+        }
+        if let Some(granted) = granted(access, "groups", &group["uuid"]) {
+            let permissions = permissions.get_or_insert_with(Vec::new);
+            for permission in granted {
+                if !permissions.contains(&permission) {
+                    permissions.push(permission);
+                }
+            }
+        }
+    }
+    permissions
+}
+
+//the permissions granted to `uuid` in the `key` ("users" or "groups")
+//grants of a vault.
+fn granted(access: &Value, key: &str, uuid: &Value) -> Option<Vec<String>> {
+    let grant = access[key]
+        .as_array()?
+        .iter()
+        .find(|grant| &grant["uuid"] == uuid)?;
+    Some(match grant["permissions"].as_array() {
+        Some(permissions) => permissions
+            .iter()
+            .filter_map(|p| p.as_str().map(String::from))
+            .collect(),
+        None => DEFAULT_PERMISSIONS.iter().map(|p| p.to_string()).collect(),
+    })
+}
+
+fn grant(vault: &mut Vault, key: &str, uuid: Value, args: &[String]) -> CmdResult {
+    let index = find_vault(vault, positional(args, 1)?)?;
+    let mut permissions = granted(&vault.vaults[index], key, &uuid).unwrap_or_default();
+    let added: Vec<String> = match flag_value(args, "--permissions") {
+        Some(added) => parse_permissions(added)?,
+        None => DEFAULT_PERMISSIONS.iter().map(|p| p.to_string()).collect(),
+    };
+    for permission in added {
+        if !permissions.contains(&permission) {
+            permissions.push(permission);
+        }
+    }
+    let access = &mut vault.vaults[index];
+    if !access[key].is_array() {
+        access[key] = json!([]);
+    }
+    let grants = access[key].as_array_mut().unwrap();
+    grants.retain(|grant| grant["uuid"] != uuid);
+    grants.push(json!({"uuid": uuid, "permissions": permissions}));
+    Ok(Vec::new())
+}
+
+//revoke `--permissions`, or all access when they are not given.
+fn revoke(vault: &mut Vault, key: &str, uuid: Value, args: &[String]) -> CmdResult {
+    let index = find_vault(vault, positional(args, 1)?)?;
+    let mut permissions = granted(&vault.vaults[index], key, &uuid)
+        .ok_or_else(|| "it doesn't have access to the vault".to_string())?;
+    match flag_value(args, "--permissions") {
+        Some(revoked) => {
+            let revoked = parse_permissions(revoked)?;
+            permissions.retain(|permission| !revoked.contains(permission));
+        }
+        None => permissions.clear(),
+    }
+    let grants = vault.vaults[index][key].as_array_mut().unwrap();
+    grants.retain(|grant| grant["uuid"] != uuid);
+    if !permissions.is_empty() {
+        grants.push(json!({"uuid": uuid, "permissions": permissions}));
+    }
+    Ok(Vec::new())
+}
+
+fn parse_permissions(permissions: &str) -> Result<Vec<String>, String> {
+    permissions
+        .split(',')
+        .map(|permission| {
+            if PERMISSIONS.contains(&permission) {
+                Ok(permission.to_string())
+            } else {
+                Err(format!("\"{}\" isn't a valid permission", permission))
+            }
+        })
+        .collect()
+}
+
+fn list_groups(vault: &Vault, args: &[String]) -> CmdResult {
+    let mut groups: Vec<Value> = vault.groups.iter().map(strip_group).collect();
+    if let Some(key) = flag_value(args, "--vault") {
+        let access = &vault.vaults[find_vault(vault, key)?];
+        groups = groups
+            .into_iter()
+            .filter_map(|mut group| {
+                group["permissions"] = json!(granted(access, "groups", &group["uuid"])?);
+                Some(group)
+            })
+            .collect();
+    }
+    if let Some(key) = flag_value(args, "--user") {
+        let user = &vault.users[find_user(vault, key)?]["uuid"];
+        groups = vault
+            .groups
+            .iter()
+            .filter_map(|group| {
+                let member = group["members"]
+                    .as_array()?
+                    .iter()
+                    .find(|member| &member["uuid"] == user)?;
+                let mut group = strip_group(group);
+                group["role"] = member["role"].clone();
+                Some(group)
+            })
+            .filter(|group| groups.iter().any(|g| g["uuid"] == group["uuid"]))
+            .collect();
+    }
+    Ok(to_json(&groups))
+}
+
+fn add_user_to_group(vault: &mut Vault, args: &[String]) -> CmdResult {
+    let user = vault.users[find_user(vault, positional(args, 0)?)?]["uuid"].clone();
+    let index = find_group(vault, positional(args, 1)?)?;
+    let role = match flag_value(args, "--role").unwrap_or("member") {
+        "member" => "MEMBER",
+        "manager" => "MANAGER",
+        role => return Err(format!("invalid role \"{}\"", role)),
+    };
+    let group = &mut vault.groups[index];
+    if !group["members"].is_array() {
+        group["members"] = json!([]);
+    }
+    let members = group["members"].as_array_mut().unwrap();
+    members.retain(|member| member["uuid"] != user);
+    members.push(json!({"uuid": user, "role": role}));
+    Ok(Vec::new())
+}
+
+//the members are fake-op's own bookkeeping, `op` does not print them.
+fn strip_group(group: &Value) -> Value {
+    let mut group = group.clone();
+    if let Some(group) = group.as_object_mut() {
+        group.remove("members");
+    }
+    group
+}
+
+fn find_group(vault: &Vault, key: &str) -> Result<usize, String> {
+    vault
+        .groups
+        .iter()
+        .position(|g| g["uuid"] == key || g["name"] == key)
+        .ok_or_else(|| {
+            format!(
+                "\"{}\" doesn't seem to be a group in this account. Specify the group with its UUID or name.",
+                key
+            )
+        })
+}
+
+fn find_user(vault: &Vault, key: &str) -> Result<usize, String> {
+    vault
+        .users
+        .iter()
+        .position(|user| user["uuid"] == key || user["email"] == key)
+        .ok_or_else(|| format!("\"{}\" doesn't seem to be a user", key))
+}
+
+fn find_item(vault: &Vault, key: &str) -> Result<usize, String> {
+    find(vault.items.iter(), key).ok_or_else(|| {
+        format!(
+            "\"{}\" doesn't seem to be an item. Specify the item with its UUID, name, or domain.",
+            key
+        )
+    })?
+}
+
+fn find_document(vault: &Vault, key: &str) -> Result<usize, String> {
+    find(vault.documents.iter().map(|doc| &doc.meta), key).ok_or_else(|| {
+        format!(
+            "\"{}\" doesn't seem to be an item. Specify the item with its UUID, name, or domain.",
+            key
+        )
+    })?
+}
+
+//find an item by uuid or title. None when nothing matches,
+//an error when the title matches more than one item.
+//Items in the trash or the archive are only found by uuid.
+fn find<'a>(items: impl Iterator<Item = &'a Value>, key: &str) -> Option<Result<usize, String>> {
+    let matches: Vec<usize> = items
+        .enumerate()
+        .filter(|(_, item)| {
+            item["uuid"] == key
+                || (item["overview"]["title"] == key && !is_trashed(item) && !is_archived(item))
+        })
+        .map(|(index, _)| index)
+        .collect();
+    match matches.as_slice() {
+        [] => None,
+        [index] => Some(Ok(*index)),
+        _ => Some(Err(format!(
+            "More than one item matches \"{}\". Try again and specify the item by its UUID",
+            key
+        ))),
+    }
+}
+
+fn is_trashed(item: &Value) -> bool {
+    item["trashed"] == "Y"
+}
+
+fn is_archived(item: &Value) -> bool {
+    item["archived"] == true
+}
+
+fn strip_totp(item: &Value) -> Value {
+    let mut item = item.clone();
+    if let Some(item) = item.as_object_mut() {
+        item.remove("totp");
+    }
+    item
+}
+
+fn strip_item(item: &Value) -> Value {
+    let mut item = strip_totp(item);
+    if let Some(item) = item.as_object_mut() {
+        item.remove("details");
+    }
+    item
+}
+
+fn list_user(user: &Value) -> Value {
+    let mut listed = serde_json::Map::new();
+    for key in [
+        "uuid",
+        "firstName",
+        "lastName",
+        "name",
+        "email",
+        "avatar",
+        "state",
+        "type",
+    ] {
+        listed.insert(key.to_string(), user[key].clone());
+    }
+    Value::Object(listed)
+}
+
+//pull `--session <token>` out of the args, the session is read from
+//`OP_SESSION_<shorthand>` otherwise.
+fn split_session(args: Vec<String>) -> (Vec<String>, Option<String>) {
+    let mut rest = Vec::with_capacity(args.len());
+    let mut session = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--session" {
+            session = args.next();
+        } else {
+            rest.push(arg);
+        }
+    }
+    (rest, session)
+}
+
+fn positional(args: &[String], index: usize) -> Result<&str, String> {
+    positionals(args)
+        .get(index)
+        .copied()
+        .ok_or_else(|| "missing argument".to_string())
+}
+
+fn positionals(args: &[String]) -> Vec<&str> {
+    let mut positionals = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            if !arg.contains('=') && !is_switch(arg) {
+                args.next();
+            }
+        } else {
+            positionals.push(arg.as_str());
+        }
+    }
+    positionals
+}
+
+fn is_switch(flag: &str) -> bool {
+    matches!(
+        flag,
+        "--raw" | "--include-trash" | "--include-archive" | "--generate-password" | "--archive"
+    )
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).map(String::as_str)
+}
+
+fn to_json<T: Serialize>(value: &T) -> Vec<u8> {
+    format!("{}\n", serde_json::to_string(value).unwrap()).into_bytes()
+}
+
+fn timestamp() -> String {
+    Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn new_uuid() -> String {
+    random_string(26, "abcdefghijklmnopqrstuvwxyz234567")
+}
+
+fn random_string(len: usize, alphabet: &str) -> String {
+    let alphabet = alphabet.as_bytes();
+    let state = RandomState::new();
+    (0..len)
+        .map(|i| {
+            let mut hasher = state.build_hasher();
+            hasher.write_usize(i);
+            hasher.write_u128(Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128);
+            alphabet[hasher.finish() as usize % alphabet.len()] as char
+        })
+        .collect()
+}
+
+fn vault_path() -> PathBuf {
+    std::env::var_os("FAKE_OP_VAULT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("fake-op-vault.json"))
+}
+
+fn load_vault(path: &Path) -> Result<Vault, String> {
+    match fs::read(path) {
+        Ok(content) => {
+            serde_json::from_slice(&content).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(seed_vault()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn save_vault(path: &Path, vault: &Vault) -> Result<(), String> {
+    let content = serde_json::to_vec_pretty(vault).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn seed_vault() -> Vault {
+    let password =
+        std::env::var("FAKE_OP_PASSWORD").unwrap_or_else(|_| DEFAULT_PASSWORD.to_string());
+    let now = timestamp();
+    let vault_uuid = new_uuid();
+    let user_uuid = new_uuid().to_uppercase();
+    let admins_uuid = new_uuid();
+    let mut vault = Vault {
+        shorthand: "my".to_string(),
+        password,
+        sessions: Vec::new(),
+        account: json!({
+            "uuid": new_uuid().to_uppercase(),
+            "name": "my",
+            "type": "I",
+            "state": "A",
+            "avatar": "",
+            "domain": "my",
+            "attrVersion": 1,
+            "createdAt": now,
+            "baseAvatarURL": "https://a.1passwordusercontent.com/",
+            "baseAttachmentURL": "https://f.1passwordusercontent.com/",
+            "vaultUuid": vault_uuid,
+        }),
+        items: vec![json!({
+            "uuid": new_uuid(),
+            "templateUuid": "001",
+            "trashed": "N",
+            "createdAt": now,
+            "updatedAt": now,
+            "changerUuid": user_uuid,
+            "itemVersion": 1,
+            "vaultUuid": vault_uuid,
+            "details": {
+                "fields": [
+                    {"designation": "username", "name": "username", "type": "T", "value": "me@example.com"},
+                    {"designation": "password", "name": "password", "type": "P", "value": "fake-password"},
+                ],
+                "sections": [],
+            },
+            "overview": {"title": "facebook", "url": "https://facebook.com"},
+            "totp": "123456",
+        })],
+        documents: vec![Document {
+            meta: json!({
+                "uuid": new_uuid(),
+                "templateUuid": DOCUMENT_TEMPLATE,
+                "createdAt": now,
+                "updatedAt": now,
+                "itemVersion": 1,
+                "vaultUuid": vault_uuid,
+                "overview": {"title": "new_doc.txt"},
+            }),
+            content: b"{\"created_by\":\"fake-op\"}".to_vec(),
+        }],
+        users: vec![json!({
+            "uuid": user_uuid,
+            "createdAt": now,
+            "updatedAt": now,
+            "lastAuthAt": now,
+            "firstName": "Wendy",
+            "lastName": "Appleseed",
+            "name": "Wendy Appleseed",
+            "email": "wendy@example.com",
+            "attrVersion": 1,
+            "keysetVersion": 1,
+            "language": "en",
+            "accountKeyFormat": "A3",
+            "accountKeyUuid": "Z4FVP3",
+            "combinedPermissions": 1048575,
+            "avatar": "",
+            "state": "A",
+            "type": "R",
+        })],
+        vaults: vec![json!({
+            "uuid": vault_uuid,
+            "name": "Private",
+            "desc": "",
+            "avatar": "",
+            "type": "P",
+            "attrVersion": 1,
+            "contentVersion": 1,
+            "createdAt": now,
+            "updatedAt": now,
+            "groups": [{"uuid": admins_uuid}],
+            "users": [{"uuid": user_uuid}],
+        })],
+        groups: vec![json!({
+            "uuid": admins_uuid,
+            "name": "Administrators",
+            "desc": "Administrators can manage people, groups and vaults.",
+            "state": "A",
+            "type": "A",
+            "createdAt": now,
+            "updatedAt": now,
+            "members": [{"uuid": user_uuid, "role": "MANAGER"}],
+        })],
+        events: Vec::new(),
+    };
+    push_event(&mut vault, "join", "user", &user_uuid);
+    push_event(&mut vault, "create", "vault", &vault_uuid);
+    push_event(&mut vault, "create", "group", &admins_uuid);
+    vault
+}
+
+//several fake-op processes may run at once, so the vault file is
+//guarded by a lock file next to it.
+struct VaultLock(PathBuf);
+
+impl VaultLock {
+    fn acquire(vault: &Path) -> Result<Self, String> {
+        let mut path = vault.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        for _ in 0..500 {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Self(path)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    thread::sleep(Duration::from_millis(10))
+                }
+                Err(e) => return Err(format!("{}: {}", path.display(), e)),
+            }
+        }
+        Err(format!("timed out waiting for {}", path.display()))
+    }
+}
+
+impl Drop for VaultLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
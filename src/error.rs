@@ -1,9 +1,243 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub enum Error {
     IOError(std::io::Error),
-    OPSignInError(String),
-    ItemQueryError(String),
     ItemDeserializeError(serde_json::error::Error),
+    FixtureError(String),
+    ///`op` failed. `command` is the failing subcommand, e.g. `get item`,
+    ///`status` its exit code and `stderr` what it printed.
+    OPCommandError {
+        kind: ErrorKind,
+        command: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+    ///the installed `op`, version `found`, does not support `command`
+    ///or one of its flags. It needs a version matching `needed`.
+    CliVersionUnsupported {
+        command: String,
+        needed: semver::VersionReq,
+        found: semver::Version,
+    },
+    ///`command` can not be run with a service account token,
+    ///e.g. managing users or groups.
+    ServiceAccountUnsupported {
+        command: String,
+    },
+    ///there is no session to reuse, or the session cache failed.
+    SessionError(String),
+    ///the installed `op` needs `argument` to run `command`, e.g. the
+    ///permissions to grant a user access to a vault with `op` 2.
+    MissingArgument {
+        command: String,
+        argument: String,
+    },
+}
+
+///What kind of failure an `Error` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    ///the item, document, user or account does not exist.
+    NotFound,
+    ///more than one item matches, specify it by uuid.
+    Ambiguous,
+    ///wrong master password or credentials.
+    Unauthorized,
+    ///the session token is invalid or expired.
+    SessionExpired,
+    RateLimited,
+    NetworkUnavailable,
+    PermissionDenied,
+    ///the `op` binary could not be found.
+    CliMissing,
+    ///the installed `op` does not support the subcommand or a flag.
+    CliVersionUnsupported,
+    Unknown,
+}
+
+impl Error {
+    pub(crate) fn op_command(command: &str, status: Option<i32>, stderr: &str) -> Self {
+        Self::OPCommandError {
+            kind: ErrorKind::classify(stderr),
+            command: command.to_string(),
+            status,
+            stderr: stderr.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::OPCommandError { kind, .. } => *kind,
+            Self::CliVersionUnsupported { .. } => ErrorKind::CliVersionUnsupported,
+            Self::ServiceAccountUnsupported { .. } => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Unknown,
+        }
+    }
+
+    ///whether the same command may succeed if it is run again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::SessionExpired | ErrorKind::RateLimited | ErrorKind::NetworkUnavailable
+        )
+    }
+}
+
+impl ErrorKind {
+    //classify the stderr of `op`. Unrecognized messages are `Unknown`.
+    pub(crate) fn classify(std_err: &str) -> Self {
+        //`op` quotes the names it was given, which may look like anything.
+        let err = without_quoted(&std_err.to_lowercase());
+        let status = |code: &str| has_status(&err, code);
+        match err.as_str() {
+            err if err.contains("more than one item matches") => Self::Ambiguous,
+            err if contains_any(
+                err,
+                &[
+                    "doesn't seem to be",
+                    "isn't an item",
+                    "not found",
+                    "does not exist",
+                ],
+            ) =>
+            {
+                Self::NotFound
+            }
+            err if contains_any(
+                err,
+                &[
+                    "invalid session token",
+                    "session expired",
+                    "you are not currently signed in",
+                ],
+            ) =>
+            {
+                Self::SessionExpired
+            }
+            err if status("401")
+                || contains_any(err, &["authentication required", "unauthorized"]) =>
+            {
+                Self::Unauthorized
+            }
+            err if status("429") || contains_any(err, &["too many requests", "rate limit"]) => {
+                Self::RateLimited
+            }
+            err if contains_any(
+                err,
+                &[
+                    "no such host",
+                    "connection refused",
+                    "network is unreachable",
+                    "dial tcp",
+                    "i/o timeout",
+                ],
+            ) =>
+            {
+                Self::NetworkUnavailable
+            }
+            err if status("403")
+                || contains_any(
+                    err,
+                    &["forbidden", "permission denied", "don't have permission"],
+                ) =>
+            {
+                Self::PermissionDenied
+            }
+            err if contains_any(
+                err,
+                &["unknown command", "unknown flag", "unknown shorthand flag"],
+            ) =>
+            {
+                Self::CliVersionUnsupported
+            }
+            _ => Self::Unknown,
+        }
+    }
+}
+
+fn contains_any(err: &str, patterns: &[&str]) -> bool {
+    patterns.iter().any(|pattern| err.contains(pattern))
+}
+
+//`err` with the quoted parts, e.g. `"aws key 4291"`, left out.
+fn without_quoted(err: &str) -> String {
+    err.split('"').step_by(2).collect::<Vec<_>>().join("\"\"")
+}
+
+//whether `err` is an http error with the status `code`, either
+//`[ERROR] 2021/03/20 10:11:12 401: ..` or `.. (401) ..`.
+fn has_status(err: &str, code: &str) -> bool {
+    let mut message = err.trim_start();
+    message = message
+        .strip_prefix("[error]")
+        .unwrap_or(message)
+        .trim_start();
+    //skip the timestamp, `op` 1 leaves it out of some errors.
+    if let Some((date, rest)) = message.split_once(' ') {
+        if date.contains('/') {
+            message = rest.split_once(' ').map_or("", |(_, rest)| rest);
+        }
+    }
+    message.starts_with(&format!("{}: ", code)) || err.contains(&format!("({})", code))
+}
+
+//the subcommand of an invocation, e.g. `get item` for `get item facebook --fields ..`.
+pub(crate) fn command_of(args: &[String]) -> String {
+    args.iter()
+        .take_while(|arg| !arg.starts_with('-'))
+        .take(2)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IOError(e) => write!(f, "io error: {}", e),
+            Self::ItemDeserializeError(e) => write!(f, "failed to deserialize op output: {}", e),
+            Self::FixtureError(msg) => write!(f, "fixture error: {}", msg),
+            Self::SessionError(msg) => write!(f, "session error: {}", msg),
+            Self::OPCommandError {
+                kind,
+                command,
+                status,
+                stderr,
+            } => {
+                write!(f, "`op {}` failed ({:?}", command, kind)?;
+                if let Some(status) = status {
+                    write!(f, ", exit status {}", status)?;
+                }
+                write!(f, "): {}", stderr.trim())
+            }
+            Self::CliVersionUnsupported {
+                command,
+                needed,
+                found,
+            } => write!(
+                f,
+                "`op {}` needs op {}, the installed op is {}",
+                command, needed, found
+            ),
+            Self::ServiceAccountUnsupported { command } => {
+                write!(f, "`op {}` can not be run by a service account", command)
+            }
+            Self::MissingArgument { command, argument } => {
+                write!(f, "`op {}` needs the {}", command, argument)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IOError(e) => Some(e),
+            Self::ItemDeserializeError(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -0,0 +1,318 @@
+//! Every `op` invocation goes through an [`Executor`].
+//!
+//! [`ProcessExecutor`] spawns the real 1password-cli and is what
+//! [`OpCLI::new_with_pass`](crate::OpCLI::new_with_pass) uses.
+//! [`ScriptedExecutor`] answers from an in-memory script, so the builders
+//! can be exercised offline against canned JSON.
+use crate::error::{command_of, Error, ErrorKind};
+use crate::Result;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::Child;
+use tokio::process::Command;
+
+pub(crate) const REDACTED: &str = "<redacted>";
+
+///One `op` invocation: its arguments, what is written to its stdin,
+///the environment variables to set and to remove for the child process,
+///and the directory it runs in.
+///Debug redacts stdin, session tokens in args and session and service
+///account token variables in env.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Invocation {
+    pub args: Vec<String>,
+    pub stdin: Option<Vec<u8>>,
+    pub env: Vec<(String, String)>,
+    pub env_remove: Vec<String>,
+    pub current_dir: Option<PathBuf>,
+}
+
+impl Invocation {
+    pub fn new(args: Vec<String>) -> Self {
+        Self {
+            args,
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Debug for Invocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let env: Vec<(&str, &str)> = self
+            .env
+            .iter()
+            .map(|(key, value)| match is_session_env(key) {
+                true => (key.as_str(), REDACTED),
+                false => (key.as_str(), value.as_str()),
+            })
+            .collect();
+        f.debug_struct("Invocation")
+            .field("args", &redact_args(&self.args))
+            .field("stdin", &self.stdin.as_ref().map(|_| REDACTED))
+            .field("env", &env)
+            .field("env_remove", &self.env_remove)
+            .field("current_dir", &self.current_dir)
+            .finish()
+    }
+}
+
+///return a copy of `args` with the value of `--session`, the `--template`
+///file of `create item`, which is named anew on every run, and the values
+///of the `edit item` (`item edit` for `op` 2) assignments redacted.
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut after_secret = false;
+    let edit_item = matches!(
+        (
+            args.first().map(String::as_str),
+            args.get(1).map(String::as_str)
+        ),
+        (Some("edit"), Some("item")) | (Some("item"), Some("edit"))
+    );
+    for (i, arg) in args.iter().enumerate() {
+        //`edit item <item> <field>=<value>..`
+        if i >= 3 && edit_item && !arg.starts_with('-') {
+            if let Some((field, _)) = arg.split_once('=') {
+                redacted.push(format!("{}={}", field, REDACTED));
+                continue;
+            }
+        }
+        if after_secret {
+            redacted.push(REDACTED.to_string());
+            after_secret = false;
+            continue;
+        }
+        match arg.as_str() {
+            "--session" | "--template" => after_secret = true,
+            arg if arg.starts_with("--session=") => {
+                redacted.push(format!("--session={}", REDACTED));
+                continue;
+            }
+            _ => {}
+        }
+        redacted.push(arg.to_string());
+    }
+    redacted
+}
+
+//the session of an account, or the token of a service account.
+fn is_session_env(key: &str) -> bool {
+    key.starts_with("OP_SESSION_") || key == "OP_SERVICE_ACCOUNT_TOKEN"
+}
+
+///What an `op` invocation produced.
+///`status` is the exit code, it is `None` when the process was killed by a signal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: Option<i32>,
+}
+
+impl ExecOutput {
+    ///a successful invocation which printed `stdout`.
+    pub fn success(stdout: impl Into<Vec<u8>>) -> Self {
+        Self {
+            stdout: stdout.into(),
+            stderr: Vec::new(),
+            status: Some(0),
+        }
+    }
+
+    ///a failed invocation which exited with `code` and printed `stderr`.
+    pub fn failure(code: i32, stderr: impl Into<Vec<u8>>) -> Self {
+        Self {
+            stdout: Vec::new(),
+            stderr: stderr.into(),
+            status: Some(code),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Executor: Send + Sync {
+    async fn exec(&self, invocation: Invocation) -> Result<ExecOutput>;
+
+    ///like `exec`, but stdout is written to `stdout` instead of being
+    ///returned. The default implementation buffers the whole output,
+    ///backends which can stream it should override this.
+    async fn exec_to(
+        &self,
+        invocation: Invocation,
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<ExecOutput> {
+        let mut output = self.exec(invocation).await?;
+        if output.is_success() {
+            stdout.write_all(&output.stdout).await?;
+            stdout.flush().await?;
+        }
+        output.stdout.clear();
+        Ok(output)
+    }
+}
+
+///The default backend, runs `op` as a tokio child process.
+#[derive(Debug, Clone)]
+pub struct ProcessExecutor {
+    program: PathBuf,
+}
+
+impl ProcessExecutor {
+    ///run the binary at `program` instead of the `op` found in PATH.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+}
+
+impl Default for ProcessExecutor {
+    fn default() -> Self {
+        Self::new("op")
+    }
+}
+
+impl ProcessExecutor {
+    //spawn `op` and write the invocation's stdin to it.
+    async fn spawn(&self, invocation: Invocation) -> Result<Child> {
+        let stdin = if invocation.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let mut command = Command::new(&self.program);
+        for key in &invocation.env_remove {
+            command.env_remove(key);
+        }
+        if let Some(dir) = &invocation.current_dir {
+            command.current_dir(dir);
+        }
+        let mut child = command
+            .args(&invocation.args)
+            .envs(invocation.env.iter().map(|(key, value)| (key, value)))
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Error::OPCommandError {
+                    kind: ErrorKind::CliMissing,
+                    command: command_of(&invocation.args),
+                    status: None,
+                    stderr: format!("{}: {}", self.program.display(), e),
+                },
+                _ => Error::IOError(e),
+            })?;
+        if let (Some(input), Some(mut stdin)) = (invocation.stdin, child.stdin.take()) {
+            //`op` may exit without reading stdin, e.g. when it rejects the
+            //arguments. Its stderr tells why.
+            match stdin.write_all(&input).await {
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(child)
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for ProcessExecutor {
+    async fn exec(&self, invocation: Invocation) -> Result<ExecOutput> {
+        let child = self.spawn(invocation).await?;
+        let output = child.wait_with_output().await?;
+        Ok(ExecOutput {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            status: output.status.code(),
+        })
+    }
+
+    async fn exec_to(
+        &self,
+        invocation: Invocation,
+        stdout: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<ExecOutput> {
+        let mut child = self.spawn(invocation).await?;
+        let mut child_stdout = child.stdout.take().unwrap();
+        let mut child_stderr = child.stderr.take().unwrap();
+        let mut stderr = Vec::new();
+        //read stderr at the same time, so `op` never blocks on a full pipe.
+        let (copied, read) = tokio::join!(
+            tokio::io::copy(&mut child_stdout, stdout),
+            child_stderr.read_to_end(&mut stderr)
+        );
+        copied?;
+        read?;
+        stdout.flush().await?;
+        let status = child.wait().await?;
+        Ok(ExecOutput {
+            stdout: Vec::new(),
+            stderr,
+            status: status.code(),
+        })
+    }
+}
+
+///An in-memory backend which answers each invocation with the next
+///scripted output, and remembers every invocation it received.
+///Clones share the same script.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedExecutor {
+    script: Arc<Mutex<Script>>,
+}
+
+#[derive(Debug, Default)]
+struct Script {
+    outputs: VecDeque<ExecOutput>,
+    invocations: Vec<Invocation>,
+}
+
+impl ScriptedExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///queue an output for the next invocation.
+    pub fn push(&self, output: ExecOutput) -> &Self {
+        self.script.lock().unwrap().outputs.push_back(output);
+        self
+    }
+
+    ///queue a successful output printing `stdout`.
+    pub fn push_success(&self, stdout: impl Into<Vec<u8>>) -> &Self {
+        self.push(ExecOutput::success(stdout))
+    }
+
+    ///every invocation received so far, oldest first.
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.script.lock().unwrap().invocations.clone()
+    }
+
+    ///the number of queued outputs which have not been used yet.
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap().outputs.len()
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for ScriptedExecutor {
+    async fn exec(&self, invocation: Invocation) -> Result<ExecOutput> {
+        let mut script = self.script.lock().unwrap();
+        let output = script.outputs.pop_front().ok_or_else(|| {
+            Error::IOError(std::io::Error::other(format!(
+                "no scripted output left for `op {}`",
+                redact_args(&invocation.args).join(" ")
+            )))
+        })?;
+        script.invocations.push(invocation);
+        Ok(output)
+    }
+}
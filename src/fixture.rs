@@ -0,0 +1,207 @@
+//! Record `op` invocations to a fixture file, and replay them later.
+//!
+//! [`RecordingExecutor`] wraps another executor and appends every invocation
+//! to a JSON file, with the session token redacted. [`ReplayExecutor`] serves
+//! those fixtures back in the same order, and fails with a diff when the
+//! requested args are not the recorded ones.
+use crate::error::Error;
+use crate::executor::{redact_args, ExecOutput, Executor, Invocation, REDACTED};
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+///One recorded `op` invocation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub args: Vec<String>,
+    #[serde(with = "bytes")]
+    pub stdout: Vec<u8>,
+    #[serde(with = "bytes")]
+    pub stderr: Vec<u8>,
+    pub status: Option<i32>,
+}
+
+impl Fixture {
+    fn new(invocation: &Invocation, output: &ExecOutput) -> Self {
+        //the output of signin is the session token itself.
+        let stdout = if is_signin(&invocation.args) && output.is_success() {
+            REDACTED.as_bytes().to_vec()
+        } else {
+            output.stdout.clone()
+        };
+        Self {
+            args: redact_args(&invocation.args),
+            stdout,
+            stderr: output.stderr.clone(),
+            status: output.status,
+        }
+    }
+}
+
+impl From<Fixture> for ExecOutput {
+    fn from(fixture: Fixture) -> Self {
+        Self {
+            stdout: fixture.stdout,
+            stderr: fixture.stderr,
+            status: fixture.status,
+        }
+    }
+}
+
+fn is_signin(args: &[String]) -> bool {
+    args.first().map(String::as_str) == Some("signin")
+}
+
+///Runs every invocation on `inner`, and writes it to the fixture file at `path`.
+///The file is rewritten after each invocation, so it is complete even if
+///the program stops early.
+pub struct RecordingExecutor<E> {
+    inner: E,
+    path: PathBuf,
+    fixtures: Arc<Mutex<Vec<Fixture>>>,
+}
+
+impl<E: Executor> RecordingExecutor<E> {
+    pub fn new(inner: E, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            fixtures: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    ///every fixture recorded so far.
+    pub async fn fixtures(&self) -> Vec<Fixture> {
+        self.fixtures.lock().await.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: Executor> Executor for RecordingExecutor<E> {
+    async fn exec(&self, invocation: Invocation) -> Result<ExecOutput> {
+        let output = self.inner.exec(invocation.clone()).await?;
+        let mut fixtures = self.fixtures.lock().await;
+        fixtures.push(Fixture::new(&invocation, &output));
+        tokio::fs::write(&self.path, serde_json::to_vec_pretty(&*fixtures)?).await?;
+        Ok(output)
+    }
+}
+
+///Serves recorded fixtures back, in the order they were recorded.
+#[derive(Debug, Clone)]
+pub struct ReplayExecutor {
+    replay: Arc<Mutex<Replay>>,
+}
+
+#[derive(Debug)]
+struct Replay {
+    fixtures: VecDeque<Fixture>,
+    served: usize,
+}
+
+impl ReplayExecutor {
+    pub fn new(fixtures: Vec<Fixture>) -> Self {
+        Self {
+            replay: Arc::new(Mutex::new(Replay {
+                fixtures: fixtures.into(),
+                served: 0,
+            })),
+        }
+    }
+
+    ///load the fixtures written by a `RecordingExecutor`.
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = tokio::fs::read(path).await?;
+        Ok(Self::new(serde_json::from_slice(&content)?))
+    }
+
+    ///the number of fixtures which have not been replayed yet.
+    pub async fn remaining(&self) -> usize {
+        self.replay.lock().await.fixtures.len()
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for ReplayExecutor {
+    async fn exec(&self, invocation: Invocation) -> Result<ExecOutput> {
+        let args = redact_args(&invocation.args);
+        let mut replay = self.replay.lock().await;
+        let fixture = match replay.fixtures.front() {
+            Some(fixture) if fixture.args == args => replay.fixtures.pop_front().unwrap(),
+            Some(fixture) => {
+                return Err(Error::FixtureError(format!(
+                    "fixture #{} does not match the invocation:\n{}",
+                    replay.served,
+                    diff_args(&fixture.args, &args)
+                )))
+            }
+            None => {
+                return Err(Error::FixtureError(format!(
+                    "no fixture left for the invocation:\n+ op {}",
+                    args.join(" ")
+                )))
+            }
+        };
+        replay.served += 1;
+        Ok(fixture.into())
+    }
+}
+
+//show the recorded args with `-` and the requested ones with `+`,
+//and point at the first arg which differs.
+fn diff_args(recorded: &[String], requested: &[String]) -> String {
+    let position = recorded
+        .iter()
+        .zip(requested)
+        .position(|(recorded, requested)| recorded != requested)
+        .unwrap_or_else(|| recorded.len().min(requested.len()));
+    let describe = |args: &[String]| {
+        args.get(position)
+            .map(|arg| format!("`{}`", arg))
+            .unwrap_or_else(|| "nothing".to_string())
+    };
+    format!(
+        "- op {}\n+ op {}\nfirst difference at arg {}: recorded {}, requested {}",
+        recorded.join(" "),
+        requested.join(" "),
+        position,
+        describe(recorded),
+        describe(requested)
+    )
+}
+
+//stdout is stored as a string when it is valid UTF-8, so the fixture
+//files stay readable, and as an array of bytes otherwise.
+mod bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Bytes {
+        Text(String),
+        Raw(Vec<u8>),
+    }
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => serializer.collect_seq(bytes),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match Bytes::deserialize(deserializer)? {
+            Bytes::Text(text) => text.into_bytes(),
+            Bytes::Raw(raw) => raw,
+        })
+    }
+}
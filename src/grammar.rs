@@ -0,0 +1,259 @@
+//! 1password-cli 2 reordered its commands, `op get item` became
+//! `op item get --format json`. The builders emit the v1 arguments,
+//! which are checked against the installed `op` version and translated
+//! here when it is version 2.
+use crate::error::{command_of, Error};
+use crate::Result;
+use semver::{Version, VersionReq};
+
+///The command grammar of the installed `op`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Grammar {
+    V1,
+    V2,
+}
+
+//parse what `op --version` prints, e.g. `1.12.4` or `2.0.0-beta.8`.
+//A missing minor or patch version is taken as 0.
+pub(crate) fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+    Version::parse(version).ok().or_else(|| {
+        let mut parts: Vec<&str> = version.split('.').collect();
+        if parts.len() >= 3 || parts.iter().any(|part| part.parse::<u64>().is_err()) {
+            return None;
+        }
+        parts.resize(3, "0");
+        Version::parse(&parts.join(".")).ok()
+    })
+}
+
+//the versions of `op` which support a command or flag the builders emit.
+//Every other command is supported by all versions.
+fn requirement(args: &[String]) -> Option<&'static str> {
+    let verb = args.first().map(String::as_str).unwrap_or_default();
+    let noun = args.get(1).map(String::as_str).unwrap_or_default();
+    let has = |flag: &str| args.iter().any(|arg| arg == flag);
+    match (verb, noun) {
+        //`op` 2 has no equivalent of these.
+        ("list", "events") | ("delete", "trash") | ("restore", _) => Some("<2"),
+        //the details are read from `--template`.
+        ("create", "item") => Some(">=1.8"),
+        //the v1 cli can not move items.
+        ("move", "item") => Some(">=2"),
+        ("list", "items") if has("--include-trash") => Some(">=1.7, <2"),
+        ("list", "items") if has("--include-archive") => Some(">=1.7"),
+        ("delete", "item") if has("--archive") => Some(">=1.7"),
+        ("edit", "document") => Some(">=1.8"),
+        ("edit", "item") => Some(">=1.6"),
+        ("list", "templates") | ("get", "template") => Some(">=1.6"),
+        _ => None,
+    }
+}
+
+///fail with `CliVersionUnsupported` before running a command
+///`version` does not support.
+pub(crate) fn check(args: &[String], version: &Version) -> Result<()> {
+    match requirement(args) {
+        Some(needed) => require(&command_of(args), needed, version),
+        None => Ok(()),
+    }
+}
+
+///fail with `CliVersionUnsupported` when `version` can not authenticate
+///with a service account token.
+pub(crate) fn check_service_account(version: &Version) -> Result<()> {
+    require("service account", ">=2.18", version)
+}
+
+fn require(command: &str, needed: &str, version: &Version) -> Result<()> {
+    let needed = VersionReq::parse(needed).expect("valid version requirement");
+    match needed.matches(version) {
+        true => Ok(()),
+        false => Err(Error::CliVersionUnsupported {
+            command: command.to_string(),
+            needed,
+            found: version.clone(),
+        }),
+    }
+}
+
+impl Grammar {
+    pub(crate) fn of(version: &Version) -> Self {
+        match version.major {
+            0 | 1 => Self::V1,
+            _ => Self::V2,
+        }
+    }
+
+    pub(crate) fn signin_args(self, account: &str) -> Vec<String> {
+        let args: &[&str] = match self {
+            Self::V1 => &["signin", account, "--raw"],
+            Self::V2 => &["signin", "--account", account, "--raw"],
+        };
+        to_args(args)
+    }
+
+    //add an account to `op` and sign in to it, the secret key and the
+    //master password are given on stdin. `op` 2 only adds it, it is signed
+    //in to afterwards.
+    pub(crate) fn add_account_args(
+        self,
+        address: &str,
+        email: &str,
+        shorthand: &str,
+    ) -> Vec<String> {
+        let args: &[&str] = match self {
+            Self::V1 => &["signin", address, email, "--shorthand", shorthand, "--raw"],
+            Self::V2 => &[
+                "account",
+                "add",
+                "--address",
+                address,
+                "--email",
+                email,
+                "--shorthand",
+                shorthand,
+            ],
+        };
+        to_args(args)
+    }
+
+    ///translate the v1 `args` a builder made to this grammar.
+    pub(crate) fn args(self, args: Vec<String>, version: &Version) -> Result<Vec<String>> {
+        match self {
+            Self::V1 => Ok(args),
+            Self::V2 => v2_args(args, version),
+        }
+    }
+
+    ///translate `add user <user> <vault> ..` or `remove user ..`, which
+    ///grant or revoke access to a vault. `op` 1 tells a vault from a group
+    ///by its name, `op` 2 has separate commands.
+    pub(crate) fn vault_access_args(self, args: Vec<String>, _: &Version) -> Result<Vec<String>> {
+        match self {
+            Self::V1 => Ok(args),
+            Self::V2 => {
+                //`op` 2 needs the permissions to grant or revoke.
+                if !args.iter().any(|arg| arg == "--permissions") {
+                    return Err(Error::MissingArgument {
+                        command: command_of(&args),
+                        argument: "permissions".to_string(),
+                    });
+                }
+                let mut v2 = vault_access(&args[0], &args[1], &args[2..]);
+                v2.extend(to_args(&["--format", "json"]));
+                Ok(v2)
+            }
+        }
+    }
+}
+
+//the commands `op` 2 lacks were already rejected by `check`.
+fn v2_args(args: Vec<String>, version: &Version) -> Result<Vec<String>> {
+    let unsupported = || Error::CliVersionUnsupported {
+        command: command_of(&args),
+        needed: VersionReq::parse("<2").expect("valid version requirement"),
+        found: version.clone(),
+    };
+    let verb = args.first().map(String::as_str).unwrap_or_default();
+    let noun = args.get(1).map(String::as_str).unwrap_or_default();
+    let rest = &args[2.min(args.len())..];
+    let mut v2 = match (verb, noun) {
+        //these take the user in place of a subcommand.
+        ("confirm" | "suspend" | "reactivate", _) => concat(&["user", verb], &args[1..]),
+        ("get", "account") => concat(&["account", "get"], rest),
+        //the whole item is printed, `--fields` would print a list of fields instead.
+        ("get", "item") => concat(&["item", "get"], &without_flag(rest, "--fields")),
+        //these print the raw totp and document, not json.
+        ("get", "totp") => return Ok(concat(&["item", "get"], &concat(rest, &["--otp"]))),
+        ("get", "document") => {
+            let rest = rename_flag(rest, "--output", "--out-file");
+            return Ok(concat(&["document", "get"], &rest));
+        }
+        ("get", "template") => concat(&["item", "template", "get"], rest),
+        ("list", "templates") => concat(&["item", "template", "list"], rest),
+        //the vault's groups and users are listed with their permissions.
+        ("list", "groups" | "users") if flag_value(rest, "--vault").is_some() => {
+            let vault = flag_value(rest, "--vault").unwrap_or_default();
+            let rest = without_flag(rest, "--vault");
+            concat(&["vault", singular(noun), "list", vault], &rest)
+        }
+        //`create item <category> --template <file> ..`, the file is written
+        //in the v2 format.
+        ("create", "item") if !rest.is_empty() => {
+            concat(&["item", "create", "--category", &rest[0]], &rest[1..])
+        }
+        ("move", "item") => concat(
+            &["item", "move"],
+            &rename_flag(rest, "--vault", "--current-vault"),
+        ),
+        ("create", "user") if rest.len() >= 2 => {
+            let flags = concat(&["--email", &rest[0], "--name", &rest[1]], &rest[2..]);
+            concat(&["user", "provision"], &flags)
+        }
+        (
+            "get" | "list" | "create" | "edit" | "delete",
+            "item" | "items" | "document" | "documents" | "user" | "users" | "vault" | "vaults"
+            | "group" | "groups",
+        ) => concat(&[singular(noun), verb], rest),
+        //`add user` adds to a group, `vault_access_args` translates the
+        //ones which grant access to a vault.
+        ("add" | "remove", "group") if rest.len() >= 2 => vault_access(verb, noun, rest),
+        ("add" | "remove", "user") if rest.len() >= 2 => {
+            let grant = if verb == "add" { "grant" } else { "revoke" };
+            let (member, group) = (&rest[0], &rest[1]);
+            let cmd = ["group", "user", grant, "--group", group, "--user", member];
+            concat(&cmd, &rest[2..])
+        }
+        _ => return Err(unsupported()),
+    };
+    v2.extend(to_args(&["--format", "json"]));
+    Ok(v2)
+}
+
+//`vault <noun> grant|revoke --vault <vault> --<noun> <member> ..` for
+//`add|remove <noun> <member> <vault> ..`.
+fn vault_access(verb: &str, noun: &str, rest: &[String]) -> Vec<String> {
+    let grant = if verb == "add" { "grant" } else { "revoke" };
+    let (member, vault) = (&rest[0], &rest[1]);
+    let member_flag = format!("--{}", noun);
+    let cmd = ["vault", noun, grant, "--vault", vault, &member_flag, member];
+    concat(&cmd, &rest[2..])
+}
+
+fn singular(noun: &str) -> &str {
+    noun.strip_suffix('s').unwrap_or(noun)
+}
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn concat<S: AsRef<str>, T: AsRef<str>>(head: &[S], tail: &[T]) -> Vec<String> {
+    head.iter()
+        .map(|arg| arg.as_ref().to_string())
+        .chain(tail.iter().map(|arg| arg.as_ref().to_string()))
+        .collect()
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).map(String::as_str)
+}
+
+//`args` without `flag` and its value.
+fn without_flag(args: &[String], flag: &str) -> Vec<String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(position) => concat(&args[..position], &args[(position + 2).min(args.len())..]),
+        None => args.to_vec(),
+    }
+}
+
+fn rename_flag(args: &[String], from: &str, to: &str) -> Vec<String> {
+    args.iter()
+        .map(|arg| match arg == from {
+            true => to.to_string(),
+            false => arg.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,454 @@
+//! Typed building blocks of an item's details, used by `create item` and
+//! `edit item`.
+use crate::output::GetTemplate;
+use crate::secret::Secret;
+use crate::Result;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::AsyncWriteExt;
+
+///The category, or template, an item is created from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Login,
+    Password,
+    SecureNote,
+    CreditCard,
+    Identity,
+    Document,
+    SoftwareLicense,
+    BankAccount,
+    Database,
+    DriverLicense,
+    OutdoorLicense,
+    Membership,
+    Passport,
+    RewardProgram,
+    SocialSecurityNumber,
+    WirelessRouter,
+    Server,
+    EmailAccount,
+    ApiCredential,
+    MedicalRecord,
+    SshKey,
+}
+
+impl Category {
+    ///the name `op` knows the category by, e.g. `Secure Note`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Login => "Login",
+            Self::Password => "Password",
+            Self::SecureNote => "Secure Note",
+            Self::CreditCard => "Credit Card",
+            Self::Identity => "Identity",
+            Self::Document => "Document",
+            Self::SoftwareLicense => "Software License",
+            Self::BankAccount => "Bank Account",
+            Self::Database => "Database",
+            Self::DriverLicense => "Driver License",
+            Self::OutdoorLicense => "Outdoor License",
+            Self::Membership => "Membership",
+            Self::Passport => "Passport",
+            Self::RewardProgram => "Reward Program",
+            Self::SocialSecurityNumber => "Social Security Number",
+            Self::WirelessRouter => "Wireless Router",
+            Self::Server => "Server",
+            Self::EmailAccount => "Email Account",
+            Self::ApiCredential => "API Credential",
+            Self::MedicalRecord => "Medical Record",
+            Self::SshKey => "SSH Key",
+        }
+    }
+
+    ///the uuid of the category's template, e.g. `001` for `Login`.
+    pub fn template_uuid(&self) -> &'static str {
+        match self {
+            Self::Login => "001",
+            Self::CreditCard => "002",
+            Self::SecureNote => "003",
+            Self::Identity => "004",
+            Self::Password => "005",
+            Self::Document => "006",
+            Self::SoftwareLicense => "100",
+            Self::BankAccount => "101",
+            Self::Database => "102",
+            Self::DriverLicense => "103",
+            Self::OutdoorLicense => "104",
+            Self::Membership => "105",
+            Self::Passport => "106",
+            Self::RewardProgram => "107",
+            Self::SocialSecurityNumber => "108",
+            Self::WirelessRouter => "109",
+            Self::Server => "110",
+            Self::EmailAccount => "111",
+            Self::ApiCredential => "112",
+            Self::MedicalRecord => "113",
+            Self::SshKey => "114",
+        }
+    }
+
+    ///the category of an item's `template_uuid`, None for unknown templates.
+    pub fn from_template_uuid(uuid: &str) -> Option<Self> {
+        CATEGORIES
+            .iter()
+            .find(|category| category.template_uuid() == uuid)
+            .copied()
+    }
+
+    //`op` 2 prints the category instead of the template uuid, e.g. `SECURE_NOTE`.
+    pub(crate) fn from_v2_name(name: &str) -> Option<Self> {
+        CATEGORIES
+            .iter()
+            .find(|category| category.name().to_uppercase().replace(' ', "_") == name)
+            .copied()
+    }
+}
+
+const CATEGORIES: [Category; 21] = [
+    Category::Login,
+    Category::Password,
+    Category::SecureNote,
+    Category::CreditCard,
+    Category::Identity,
+    Category::Document,
+    Category::SoftwareLicense,
+    Category::BankAccount,
+    Category::Database,
+    Category::DriverLicense,
+    Category::OutdoorLicense,
+    Category::Membership,
+    Category::Passport,
+    Category::RewardProgram,
+    Category::SocialSecurityNumber,
+    Category::WirelessRouter,
+    Category::Server,
+    Category::EmailAccount,
+    Category::ApiCredential,
+    Category::MedicalRecord,
+    Category::SshKey,
+];
+
+///The type of a section field, it decides how the apps show the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    String,
+    Concealed,
+    Email,
+    Url,
+    Phone,
+    Date,
+    MonthYear,
+}
+
+impl FieldType {
+    //the type of an `edit item` assignment, `section.field[type]=value`.
+    fn assignment_type(&self) -> &'static str {
+        match self {
+            Self::String => "text",
+            Self::Concealed => "password",
+            Self::Email => "email",
+            Self::Url => "url",
+            Self::Phone => "phone",
+            Self::Date => "date",
+            Self::MonthYear => "monthYear",
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Concealed => "concealed",
+            Self::Email => "email",
+            Self::Url => "URL",
+            Self::Phone => "phone",
+            Self::Date => "date",
+            Self::MonthYear => "monthYear",
+        }
+    }
+}
+
+///A field of a section, `value` is kept as a `Secret`.
+#[derive(Debug, Clone)]
+pub struct Field {
+    kind: FieldType,
+    title: String,
+    value: Secret<String>,
+}
+
+impl Field {
+    pub fn new(kind: FieldType, title: &str, value: impl Into<Secret<String>>) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            value: value.into(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "k": self.kind.kind(),
+            "n": field_name(&self.title),
+            "t": self.title,
+            "v": self.value.expose(),
+        })
+    }
+}
+
+///A titled group of fields.
+#[derive(Debug, Clone)]
+pub struct Section {
+    title: String,
+    fields: Vec<Field>,
+}
+
+impl Section {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": field_name(&self.title),
+            "title": self.title,
+            "fields": self.fields.iter().map(Field::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+//the details json of an item, `op create item` reads it from a `DetailsFile`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ItemDetails {
+    pub(crate) template: Option<GetTemplate>,
+    pub(crate) username: Option<String>,
+    pub(crate) password: Option<Secret<String>>,
+    pub(crate) notes: Option<String>,
+    pub(crate) sections: Vec<Section>,
+    //custom fields which were added without a section.
+    pub(crate) fields: Vec<Field>,
+}
+
+impl ItemDetails {
+    pub(crate) fn to_json(&self) -> Value {
+        let template = self.template.clone().unwrap_or_default();
+        let mut fields: Vec<Value> = template.fields.iter().map(|f| json!(f)).collect();
+        if let Some(username) = &self.username {
+            set_field(&mut fields, "username", "T", username);
+        }
+        if let Some(password) = &self.password {
+            set_field(&mut fields, "password", "P", password.expose());
+        }
+        let mut sections: Vec<Value> = template.sections.iter().map(|s| json!(s)).collect();
+        sections.extend(self.sections.iter().map(Section::to_json));
+        if !self.fields.is_empty() {
+            sections.push(json!({
+                "name": "",
+                "title": "",
+                "fields": self.fields.iter().map(Field::to_json).collect::<Vec<_>>(),
+            }));
+        }
+        json!({
+            "notesPlain": self.notes.clone().unwrap_or(template.notes_plain),
+            "fields": fields,
+            "sections": sections,
+        })
+    }
+}
+
+//`op` 2 reads a template of `fields` and `sections` instead of the details,
+//e.g. `{"id":"password","type":"CONCEALED","purpose":"PASSWORD",..}`.
+pub(crate) fn v2_template(details: &Value) -> Value {
+    let list = |value: &Value| value.as_array().cloned().unwrap_or_default();
+    let mut fields: Vec<Value> = list(&details["fields"])
+        .iter()
+        .map(|field| {
+            let purpose = match field["designation"].as_str() {
+                Some("username") => "USERNAME",
+                Some("password") => "PASSWORD",
+                _ => "",
+            };
+            json!({
+                "id": field["name"],
+                "type": if field["type"] == "P" { "CONCEALED" } else { "STRING" },
+                "purpose": purpose,
+                "label": field["name"],
+                "value": field["value"],
+            })
+        })
+        .collect();
+    if let Some(notes) = details["notesPlain"]
+        .as_str()
+        .filter(|notes| !notes.is_empty())
+    {
+        fields.push(json!({
+            "id": "notesPlain",
+            "type": "STRING",
+            "purpose": "NOTES",
+            "label": "notesPlain",
+            "value": notes,
+        }));
+    }
+    let mut sections = Vec::new();
+    for section in list(&details["sections"]) {
+        //custom fields without a section are in one without a name.
+        let id = section["name"].as_str().unwrap_or_default();
+        if !id.is_empty() {
+            sections.push(json!({"id": id, "label": section["title"]}));
+        }
+        for field in list(&section["fields"]) {
+            let kind = match field["k"].as_str().unwrap_or_default() {
+                "monthYear" => "MONTH_YEAR".to_string(),
+                kind => kind.to_uppercase(),
+            };
+            let mut field = json!({
+                "id": field["n"],
+                "type": kind,
+                "label": field["t"],
+                "value": field["v"],
+            });
+            if !id.is_empty() {
+                field["section"] = json!({ "id": id });
+            }
+            fields.push(field);
+        }
+    }
+    json!({ "fields": fields, "sections": sections })
+}
+
+//set the value of a built-in field, which the template may already have.
+fn set_field(fields: &mut Vec<Value>, designation: &str, kind: &str, value: &str) {
+    match fields
+        .iter_mut()
+        .find(|field| field["designation"] == designation)
+    {
+        Some(field) => field["value"] = json!(value),
+        None => fields.push(json!({
+            "designation": designation,
+            "name": designation,
+            "type": kind,
+            "value": value,
+        })),
+    }
+}
+
+//The details contain the password, so they are given to `op` in a file only
+//its owner can read instead of as an argument, which shows up in the process
+//list. The file is removed when it is dropped.
+pub(crate) struct DetailsFile {
+    path: PathBuf,
+}
+
+impl DetailsFile {
+    pub(crate) async fn create(details: &Value) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "op-item-{}-{}-{}.json",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        let path = std::env::temp_dir().join(name);
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut out = options.open(&path).await?;
+        //from here on the file is ours to remove.
+        let file = Self { path };
+        let json = Secret::new(details.to_string());
+        out.write_all(json.expose().as_bytes()).await?;
+        out.flush().await?;
+        Ok(file)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for DetailsFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+///An `edit item` assignment, which sets a field to a new value.
+///
+///`op` takes assignments as arguments, so the value shows up in the
+///process list of the host while `op` runs, it is only left out of
+///`Debug` and of recorded invocations. Rotate passwords with
+///`EditItemCmd::generate_password`, which never passes the new one in.
+#[derive(Debug, Clone)]
+pub struct Assignment(Secret<String>);
+
+impl Assignment {
+    ///set a built-in field such as `username` or `password`.
+    pub fn new(field: &str, value: impl Into<Secret<String>>) -> Self {
+        Self(Secret::new(format!("{}={}", field, value.into().expose())))
+    }
+
+    ///set `field` of `section`, the field is created with `kind`
+    ///when it does not exist yet.
+    pub fn in_section(
+        section: &str,
+        field: &str,
+        kind: FieldType,
+        value: impl Into<Secret<String>>,
+    ) -> Self {
+        Self(Secret::new(format!(
+            "{}.{}[{}]={}",
+            section,
+            field,
+            kind.assignment_type(),
+            value.into().expose()
+        )))
+    }
+
+    pub(crate) fn expose(&self) -> &str {
+        self.0.expose()
+    }
+}
+
+///How `--generate-password` makes a new password. The default recipe
+///leaves it to `op`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PasswordRecipe {
+    pub length: Option<usize>,
+    pub letters: bool,
+    pub digits: bool,
+    pub symbols: bool,
+}
+
+impl PasswordRecipe {
+    //e.g. `letters,digits,32`, empty for the default recipe.
+    pub(crate) fn to_arg(self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if self.letters {
+            parts.push("letters".to_string());
+        }
+        if self.digits {
+            parts.push("digits".to_string());
+        }
+        if self.symbols {
+            parts.push("symbols".to_string());
+        }
+        if let Some(length) = self.length {
+            parts.push(length.to_string());
+        }
+        parts.join(",")
+    }
+}
+
+//`op` names fields and sections after their title, in lower case.
+fn field_name(title: &str) -> String {
+    title.to_lowercase().replace(' ', "_")
+}
@@ -1,58 +1,353 @@
 pub mod error;
+pub mod executor;
+pub mod fixture;
+mod grammar;
+pub mod item;
 pub mod output;
+pub mod secret;
+#[cfg(feature = "session-cache")]
+pub mod session_cache;
 mod tests;
 use chrono::{prelude::*, Duration};
 use error::Error;
+use executor::{ExecOutput, Executor, Invocation, ProcessExecutor};
+use futures::stream::{self, Stream, TryStreamExt};
+use grammar::Grammar;
 
-use std::process::Stdio;
-use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
+//turns the v1 arguments a builder made into the ones of the installed `op`.
+type Translate = fn(Grammar, Vec<String>, &semver::Version) -> Result<Vec<String>>;
+pub use item::Category;
+use item::{Assignment, Field, ItemDetails, PasswordRecipe, Section};
+use secret::Secret;
+#[cfg(feature = "session-cache")]
+use session_cache::SessionCache;
 
-use crate::sealed::{FirstCmd, SecondCmd};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::sync::Mutex;
+
+use crate::sealed::{FirstCmd, FromOutput, SecondCmd};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-//OpCLI have expiration_time field what is the token's expiration time.
-//Intent to implement some method to auto renew the token. //TODO
+//1password-cli's session token expires after 30 minutes of inactivity.
+//We keep a minute of margin, and renew the token when it is about to expire.
+const SESSION_LIFETIME_MINUTES: i64 = 29;
+const SESSION_RENEW_MARGIN_SECONDS: i64 = 60;
+
+///OpCLI keeps the credentials it signed in with, so it can renew the session
+///token by itself when it is expired or about to expire.
+///Clones share the same session, a renewed token is seen by all of them.
+///A session reused from elsewhere can not be renewed, and a service account
+///token is given to every command instead of a session.
 #[derive(Clone)]
 pub struct OpCLI {
+    credentials: Arc<Credentials>,
+    session: Arc<Mutex<Session>>,
+    runner: Arc<Runner>,
+}
+
+impl fmt::Debug for OpCLI {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpCLI")
+            .field("credentials", &self.credentials)
+            .field("session", &self.session)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+enum Credentials {
+    //the session is renewed by signing in again with the master password.
+    Password {
+        username: String,
+        password: Secret<String>,
+    },
+    //a session signed in elsewhere, e.g. by the `op` cli.
+    Session {
+        username: String,
+    },
+    //the token is the session, it does not expire.
+    ServiceAccount,
+}
+
+impl Credentials {
+    //what `op signin` needs, None when the session can not be renewed.
+    fn password(&self) -> Option<(&str, &Secret<String>)> {
+        match self {
+            Self::Password { username, password } => Some((username, password)),
+            Self::Session { .. } | Self::ServiceAccount => None,
+        }
+    }
+
+    fn can_signin(&self) -> bool {
+        self.password().is_some()
+    }
+
+    fn expires(&self) -> bool {
+        !matches!(self, Self::ServiceAccount)
+    }
+
+    //`op` 2 is told which account to use, a service account has only one.
+    fn account(&self) -> Option<&str> {
+        match self {
+            Self::Password { username, .. } | Self::Session { username } => Some(username),
+            Self::ServiceAccount => None,
+        }
+    }
+
+    //The session is passed to `op` through the environment, so it never
+    //shows up in the process list.
+    fn session_env(&self, session: &Secret<String>) -> (String, String) {
+        let key = match self {
+            Self::Password { username, .. } | Self::Session { username } => {
+                format!("OP_SESSION_{}", username)
+            }
+            Self::ServiceAccount => "OP_SERVICE_ACCOUNT_TOKEN".to_string(),
+        };
+        (key, session.expose().trim().to_string())
+    }
+}
+
+//Service accounts can not manage users and groups, nor who can access a
+//vault. Those commands are rejected before `op` is run.
+fn service_account_supports(args: &[String]) -> bool {
+    let verb = args.first().map(String::as_str).unwrap_or_default();
+    let noun = args.get(1).map(String::as_str).unwrap_or_default();
+    !matches!(
+        (verb, noun),
+        (_, "user" | "users" | "group" | "groups")
+            | ("list", "events")
+            | ("confirm" | "suspend" | "reactivate", _)
+    )
+}
+
+#[derive(Debug)]
+struct Session {
+    token: Secret<String>,
     expiration_time: DateTime<Utc>,
-    session: String,
+}
+
+impl Session {
+    fn is_expiring(&self) -> bool {
+        self.expiration_time - Utc::now() < Duration::seconds(SESSION_RENEW_MARGIN_SECONDS)
+    }
+
+    //every successful command resets the cli's inactivity timer.
+    fn touch(&mut self) {
+        self.expiration_time = Utc::now() + Duration::minutes(SESSION_LIFETIME_MINUTES);
+    }
 }
 
 impl OpCLI {
     #[inline]
-    pub async fn new_with_pass(username: &str, password: &str) -> Result<Self> {
-        let mut child = Command::new("op")
-            .arg("signin")
-            .arg(username)
-            .arg("--raw")
-            .stdin(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
-        let stdin = child.stdin.as_mut().unwrap();
-        stdin.write_all(password.as_bytes()).await?;
-        let output = child.wait_with_output().await?;
-        handle_op_signin_error(String::from_utf8_lossy(&output.stderr).to_string()).await?;
-        let expiration_time = Utc::now() + Duration::minutes(29);
-        Ok(Self {
-            expiration_time,
-            session: String::from_utf8_lossy(&output.stdout).to_string(),
-        })
+    pub async fn new_with_pass(
+        username: &str,
+        password: impl Into<Secret<String>>,
+    ) -> Result<Self> {
+        Self::builder().signin(username, password).await
+    }
+
+    ///same as `new_with_pass`, but every `op` invocation goes through `executor`.
+    pub async fn new_with_executor(
+        username: &str,
+        password: impl Into<Secret<String>>,
+        executor: impl Executor + 'static,
+    ) -> Result<Self> {
+        Self::builder()
+            .executor(executor)
+            .signin(username, password)
+            .await
+    }
+
+    ///authenticate every command with a service account token, which is
+    ///given to `op` as `OP_SERVICE_ACCOUNT_TOKEN`. There is no signin.
+    ///Service accounts need `op` 2.18 or later, an older `op` fails with
+    ///`Error::CliVersionUnsupported` up front. They can not manage users,
+    ///groups and vault permissions nor read the event log. Those commands
+    ///fail with `Error::ServiceAccountUnsupported` without running `op`.
+    pub async fn from_service_account_token(token: impl Into<Secret<String>>) -> Result<Self> {
+        Self::builder().service_account_token(token).await
+    }
+
+    ///reuse the session `token` of `account`, e.g. one printed by
+    ///`op signin --raw`. It is checked with `op get account`, which also
+    ///resets its expiration time. Without the master password it can not be
+    ///renewed, commands fail with `ErrorKind::SessionExpired` once it expires.
+    pub async fn from_session(account: &str, token: impl Into<Secret<String>>) -> Result<Self> {
+        Self::builder().session(account, token).await
+    }
+
+    ///reuse the session of an `OP_SESSION_<shorthand>` variable, the one of
+    ///the `OP_ACCOUNT` account when several are set. See `from_session`.
+    pub async fn from_env() -> Result<Self> {
+        Self::builder().session_from_env().await
+    }
+
+    ///add an account to `op` and sign in to it, for a machine which has never
+    ///signed in before. See `OpCLIBuilder::add_account`.
+    pub fn add_account(
+        address: &str,
+        email: &str,
+        secret_key: impl Into<Secret<String>>,
+    ) -> SigninBuilder {
+        Self::builder().add_account(address, email, secret_key)
+    }
+
+    ///whether the commands are authenticated with a service account token.
+    pub fn is_service_account(&self) -> bool {
+        matches!(*self.credentials, Credentials::ServiceAccount)
+    }
+
+    ///configure the `op` binary, its environment and working directory
+    ///before signing in.
+    pub fn builder() -> OpCLIBuilder {
+        OpCLIBuilder::default()
+    }
+
+    ///the version of the installed `op`. It is detected once with
+    ///`op --version` when signing in, unless the builder was given one.
+    pub fn cli_version(&self) -> &semver::Version {
+        &self.runner.version
+    }
+
+    ///return the token's expiration time, far in the future for a
+    ///service account token.
+    pub async fn expiration_time(&self) -> DateTime<Utc> {
+        self.session.lock().await.expiration_time
+    }
+
+    //return a valid session token, signin again if the current one is
+    //expired or about to expire. The lock is held while signing in, so
+    //concurrent callers wait for the renewed token instead of signing in too.
+    async fn session(&self) -> Result<Secret<String>> {
+        let mut session = self.session.lock().await;
+        if let Some((username, password)) = self.credentials.password() {
+            if session.is_expiring() {
+                *session = signin(&self.runner, username, password).await?;
+            }
+        }
+        Ok(session.token.clone())
+    }
+
+    //renew the session after the cli rejected `stale`. If another caller has
+    //already renewed it, the new token is returned without signing in again.
+    async fn renew_session(&self, stale: &Secret<String>) -> Result<Secret<String>> {
+        let mut session = self.session.lock().await;
+        if let Some((username, password)) = self.credentials.password() {
+            if session.token.expose() == stale.expose() {
+                *session = signin(&self.runner, username, password).await?;
+            }
+        }
+        Ok(session.token.clone())
+    }
+
+    //run `op` with a valid session and return its stdout. When `stdout` is
+    //given, the output is streamed to it instead and nothing is returned.
+    async fn run_command(
+        &self,
+        args: Vec<String>,
+        stdout: Option<&mut (dyn AsyncWrite + Unpin + Send)>,
+    ) -> Result<Vec<u8>> {
+        self.run_command_with(args, None, stdout, Grammar::args)
+            .await
+    }
+
+    //run `op` with `stdin` written to it and return its stdout.
+    async fn run_command_with_stdin(&self, args: Vec<String>, stdin: Vec<u8>) -> Result<Vec<u8>> {
+        self.run_command_with(args, Some(stdin), None, Grammar::args)
+            .await
+    }
+
+    //`translate` turns the v1 `args` into the ones of the installed `op`.
+    async fn run_command_with(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Vec<u8>>,
+        mut stdout: Option<&mut (dyn AsyncWrite + Unpin + Send)>,
+        translate: Translate,
+    ) -> Result<Vec<u8>> {
+        let session = self.session().await?;
+        let reborrowed = stdout
+            .as_mut()
+            .map(|stdout| &mut **stdout as &mut (dyn AsyncWrite + Unpin + Send));
+        let res = self
+            .exec_command(args.clone(), stdin.clone(), &session, reborrowed, translate)
+            .await;
+        let (session, out) = match res {
+            //the token can be revoked before its expiration time,
+            //so renew it and retry once. `op` fails before printing
+            //anything, so nothing was streamed yet.
+            Err(e)
+                if e.kind() == error::ErrorKind::SessionExpired
+                    && self.credentials.can_signin() =>
+            {
+                let session = self.renew_session(&session).await?;
+                let out = self
+                    .exec_command(args, stdin, &session, stdout, translate)
+                    .await?;
+                (session, out)
+            }
+            res => (session, res?),
+        };
+        self.touch_session(&session).await;
+        Ok(out)
+    }
+
+    #[inline]
+    async fn exec_command(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Vec<u8>>,
+        session: &Secret<String>,
+        stdout: Option<&mut (dyn AsyncWrite + Unpin + Send)>,
+        translate: Translate,
+    ) -> Result<Vec<u8>> {
+        if self.is_service_account() && !service_account_supports(&args) {
+            return Err(Error::ServiceAccountUnsupported {
+                command: error::command_of(&args),
+            });
+        }
+        let version = &self.runner.version;
+        grammar::check(&args, version)?;
+        let mut args = translate(self.runner.grammar(), args, version)?;
+        if let (Grammar::V2, Some(account)) = (self.runner.grammar(), self.credentials.account()) {
+            args.push("--account".to_string());
+            args.push(account.to_string());
+        }
+        let command = error::command_of(&args);
+        let env = vec![self.credentials.session_env(session)];
+        let invocation = self.runner.invocation(args, stdin, env);
+        let output = match stdout {
+            Some(stdout) => self.runner.executor.exec_to(invocation, stdout).await?,
+            None => self.runner.executor.exec(invocation).await?,
+        };
+        handle_op_error(&command, &output)?;
+        Ok(output.stdout)
+    }
+
+    async fn touch_session(&self, token: &Secret<String>) {
+        let mut session = self.session.lock().await;
+        if self.credentials.expires() && session.token.expose() == token.expose() {
+            session.touch();
+        }
     }
 
     pub fn get(&self) -> GetCmd {
         GetCmd {
             cmd: "get".to_string(),
-            session: self.session.to_string(),
+            op: self.clone(),
         }
     }
 
     pub fn create(&self) -> CreateCmd {
         CreateCmd {
             cmd: "create".to_string(),
-            session: self.session.to_string(),
+            op: self.clone(),
         }
     }
 
@@ -60,7 +355,7 @@ impl OpCLI {
     pub fn list(&self) -> ListCmd {
         ListCmd {
             cmd: "list".to_string(),
-            session: self.session.to_string(),
+            op: self.clone(),
         }
     }
 
@@ -68,15 +363,474 @@ impl OpCLI {
     pub fn delete(&self) -> DeleteCmd {
         DeleteCmd {
             cmd: "delete".to_string(),
-            session: self.session.to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn edit(&self) -> EditCmd {
+        EditCmd {
+            cmd: "edit".to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn add(&self) -> AddCmd {
+        AddCmd {
+            cmd: "add".to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn remove(&self) -> RemoveCmd {
+        RemoveCmd {
+            cmd: "remove".to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn restore(&self) -> RestoreCmd {
+        RestoreCmd {
+            cmd: "restore".to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn confirm(&self) -> ConfirmCmd {
+        ConfirmCmd {
+            cmd: "confirm".to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn suspend(&self) -> SuspendCmd {
+        SuspendCmd {
+            cmd: "suspend".to_string(),
+            op: self.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn reactivate(&self) -> ReactivateCmd {
+        ReactivateCmd {
+            cmd: "reactivate".to_string(),
+            op: self.clone(),
+        }
+    }
+}
+
+///Builds an `OpCLI`. Every `op` invocation made by it, including signin,
+///uses these settings.
+#[derive(Default)]
+pub struct OpCLIBuilder {
+    bin_path: Option<PathBuf>,
+    executor: Option<Arc<dyn Executor>>,
+    env: Vec<(String, String)>,
+    env_remove: Vec<String>,
+    config_dir: Option<PathBuf>,
+    current_dir: Option<PathBuf>,
+    cli_version: Option<String>,
+    #[cfg(feature = "session-cache")]
+    session_cache: Option<SessionCache>,
+}
+
+impl OpCLIBuilder {
+    ///run the `op` binary at `path` instead of the one found in PATH.
+    ///Ignored when a custom executor is set.
+    pub fn bin_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.bin_path = Some(path.into());
+        self
+    }
+
+    ///run every invocation through `executor` instead of spawning `op`.
+    pub fn executor(mut self, executor: impl Executor + 'static) -> Self {
+        self.executor = Some(Arc::new(executor));
+        self
+    }
+
+    ///set an environment variable for the `op` process.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    ///remove an environment variable inherited from the parent process.
+    pub fn env_remove(mut self, key: &str) -> Self {
+        self.env_remove.push(key.to_string());
+        self
+    }
+
+    ///pass `--config <dir>` to every invocation.
+    pub fn config_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config_dir = Some(dir.into());
+        self
+    }
+
+    ///run `op` in `dir`.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    ///the version of the installed `op`, e.g. `1.12.4`. By default it is
+    ///detected with `op --version` before signing in.
+    pub fn cli_version(mut self, version: &str) -> Self {
+        self.cli_version = Some(version.to_string());
+        self
+    }
+
+    ///store every session signed in with the master password in `cache`,
+    ///for `cached_session` to reuse.
+    #[cfg(feature = "session-cache")]
+    pub fn session_cache(mut self, cache: SessionCache) -> Self {
+        self.session_cache = Some(cache);
+        self
+    }
+
+    pub async fn signin(
+        self,
+        username: &str,
+        password: impl Into<Secret<String>>,
+    ) -> Result<OpCLI> {
+        let password = password.into();
+        let runner = self.build_runner().await?;
+        let session = signin(&runner, username, &password).await?;
+        let credentials = Credentials::Password {
+            username: username.to_string(),
+            password,
+        };
+        Ok(OpCLI {
+            credentials: Arc::new(credentials),
+            session: Arc::new(Mutex::new(session)),
+            runner: Arc::new(runner),
+        })
+    }
+
+    ///use a service account token instead of signing in,
+    ///see `OpCLI::from_service_account_token`.
+    pub async fn service_account_token(self, token: impl Into<Secret<String>>) -> Result<OpCLI> {
+        let runner = self.build_runner().await?;
+        grammar::check_service_account(&runner.version)?;
+        //the token does not expire, it is only revoked.
+        let session = Session {
+            token: token.into(),
+            expiration_time: DateTime::<Utc>::MAX_UTC,
+        };
+        Ok(OpCLI {
+            credentials: Arc::new(Credentials::ServiceAccount),
+            session: Arc::new(Mutex::new(session)),
+            runner: Arc::new(runner),
+        })
+    }
+
+    ///add the account of the sign-in address `address`, e.g.
+    ///`my.1password.com`, instead of signing in to an account `op` already
+    ///knows. `op` prompts for `secret_key` before the master password, both
+    ///are written to its stdin so neither shows up in the process list.
+    pub fn add_account(
+        self,
+        address: &str,
+        email: &str,
+        secret_key: impl Into<Secret<String>>,
+    ) -> SigninBuilder {
+        SigninBuilder {
+            builder: self,
+            address: address.to_string(),
+            email: email.to_string(),
+            secret_key: secret_key.into(),
+            shorthand: None,
+        }
+    }
+
+    ///reuse a session instead of signing in, see `OpCLI::from_session`.
+    pub async fn session(self, account: &str, token: impl Into<Secret<String>>) -> Result<OpCLI> {
+        let runner = self.build_runner().await?;
+        //the expiration time is filled in by the check.
+        let session = Session {
+            token: token.into(),
+            expiration_time: Utc::now(),
+        };
+        let credentials = Credentials::Session {
+            username: account.to_string(),
+        };
+        let op_cli = OpCLI {
+            credentials: Arc::new(credentials),
+            session: Arc::new(Mutex::new(session)),
+            runner: Arc::new(runner),
+        };
+        op_cli.get().account().run().await?;
+        Ok(op_cli)
+    }
+
+    ///reuse the session of an `OP_SESSION_<shorthand>` variable, see
+    ///`OpCLI::from_env`. Variables set with `env` are taken into account.
+    pub async fn session_from_env(self) -> Result<OpCLI> {
+        let (account, token) = env_session(&self.env, &self.env_remove)?;
+        self.session(&account, token).await
+    }
+
+    ///reuse the session of `account` in the session cache. A session which
+    ///is no longer valid is removed from the cache.
+    #[cfg(feature = "session-cache")]
+    pub async fn cached_session(self, account: &str) -> Result<OpCLI> {
+        let cache = self
+            .session_cache
+            .clone()
+            .ok_or_else(|| Error::SessionError("no session cache is set".to_string()))?;
+        let token = cache
+            .load(account)
+            .await?
+            .ok_or_else(|| Error::SessionError(format!("no cached session for {}", account)))?;
+        match self.session(account, token).await {
+            Err(e) if e.kind() == error::ErrorKind::SessionExpired => {
+                cache.remove(account).await?;
+                Err(e)
+            }
+            res => res,
+        }
+    }
+
+    //build the runner, with the version of `op` detected unless it was given.
+    async fn build_runner(self) -> Result<Runner> {
+        let version = self.cli_version.clone();
+        let mut runner = self.runner();
+        let version = match version {
+            Some(version) => version,
+            None => detect_version(&runner).await?,
+        };
+        runner.version = grammar::parse_version(&version).ok_or_else(|| {
+            Error::op_command(
+                "--version",
+                None,
+                &format!("unrecognized version of op: {}", version.trim()),
+            )
+        })?;
+        Ok(runner)
+    }
+
+    fn runner(self) -> Runner {
+        let bin_path = self.bin_path;
+        let executor = self.executor.unwrap_or_else(|| match bin_path {
+            Some(path) => Arc::new(ProcessExecutor::new(path)),
+            None => Arc::new(ProcessExecutor::default()),
+        });
+        Runner {
+            executor,
+            #[cfg(feature = "session-cache")]
+            session_cache: self.session_cache,
+            env: self.env,
+            env_remove: self.env_remove,
+            config_dir: self.config_dir,
+            current_dir: self.current_dir,
+            //replaced by the detected version before signing in.
+            version: semver::Version::new(0, 0, 0),
+        }
+    }
+}
+
+///Adds an account to `op` and signs in to it. Afterwards the session is
+///renewed like one of `OpCLIBuilder::signin`.
+pub struct SigninBuilder {
+    builder: OpCLIBuilder,
+    address: String,
+    email: String,
+    secret_key: Secret<String>,
+    shorthand: Option<String>,
+}
+
+impl SigninBuilder {
+    ///the shorthand `op` knows the account by. By default it is the
+    ///subdomain of the sign-in address, e.g. `my` for `my.1password.com`.
+    pub fn shorthand(mut self, shorthand: &str) -> Self {
+        self.shorthand = Some(shorthand.to_string());
+        self
+    }
+
+    ///add the account and sign in to it with the master password.
+    pub async fn signin(self, password: impl Into<Secret<String>>) -> Result<OpCLI> {
+        let password = password.into();
+        let shorthand = match self.shorthand {
+            Some(shorthand) => shorthand,
+            None => default_shorthand(&self.address)?,
+        };
+        let runner = self.builder.build_runner().await?;
+        let args = runner
+            .grammar()
+            .add_account_args(&self.address, &self.email, &shorthand);
+        //the answers to the prompts, one per line.
+        let answers = Secret::new(format!(
+            "{}\n{}",
+            self.secret_key.expose(),
+            password.expose()
+        ));
+        let session = match runner.grammar() {
+            Grammar::V1 => new_session(&runner, &shorthand, args, &answers).await?,
+            Grammar::V2 => {
+                let stdin = Some(answers.expose().as_bytes().to_vec());
+                let output = runner.exec(args, stdin, Vec::new()).await?;
+                handle_op_error("account add", &output)?;
+                signin(&runner, &shorthand, &password).await?
+            }
+        };
+        let credentials = Credentials::Password {
+            username: shorthand,
+            password,
+        };
+        Ok(OpCLI {
+            credentials: Arc::new(credentials),
+            session: Arc::new(Mutex::new(session)),
+            runner: Arc::new(runner),
+        })
+    }
+}
+
+//e.g. `my` for `https://my.1password.com`.
+fn default_shorthand(address: &str) -> Result<String> {
+    let host = address
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    match host.split_once('.') {
+        Some((subdomain, _)) if !subdomain.is_empty() => Ok(subdomain.to_string()),
+        _ => Err(Error::op_command(
+            "signin",
+            None,
+            &format!("invalid sign-in address: {}", address),
+        )),
+    }
+}
+
+//Runs `op` through the executor with the builder's settings applied.
+struct Runner {
+    executor: Arc<dyn Executor>,
+    #[cfg(feature = "session-cache")]
+    session_cache: Option<SessionCache>,
+    env: Vec<(String, String)>,
+    env_remove: Vec<String>,
+    config_dir: Option<PathBuf>,
+    current_dir: Option<PathBuf>,
+    version: semver::Version,
+}
+
+impl Runner {
+    //the builders emit v1 arguments, they are translated for `op` 2.
+    fn grammar(&self) -> Grammar {
+        Grammar::of(&self.version)
+    }
+
+    fn invocation(
+        &self,
+        mut args: Vec<String>,
+        stdin: Option<Vec<u8>>,
+        env: Vec<(String, String)>,
+    ) -> Invocation {
+        if let Some(dir) = &self.config_dir {
+            args.push("--config".to_string());
+            args.push(dir.to_string_lossy().to_string());
+        }
+        Invocation {
+            args,
+            stdin,
+            env: self.env.iter().cloned().chain(env).collect(),
+            env_remove: self.env_remove.clone(),
+            current_dir: self.current_dir.clone(),
         }
     }
+
+    async fn exec(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Vec<u8>>,
+        env: Vec<(String, String)>,
+    ) -> Result<ExecOutput> {
+        self.executor.exec(self.invocation(args, stdin, env)).await
+    }
+}
+
+//`op --version` prints e.g. `1.12.4`.
+async fn detect_version(runner: &Runner) -> Result<String> {
+    let output = runner
+        .exec(vec!["--version".to_string()], None, Vec::new())
+        .await?;
+    handle_op_error("--version", &output)?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[inline]
+async fn signin(runner: &Runner, username: &str, password: &Secret<String>) -> Result<Session> {
+    let args = runner.grammar().signin_args(username);
+    new_session(runner, username, args, password).await
+}
+
+//run a signin command, which reads the password, or whatever else it
+//prompts for, on stdin and prints the session token.
+#[cfg_attr(not(feature = "session-cache"), allow(unused_variables))]
+async fn new_session(
+    runner: &Runner,
+    username: &str,
+    args: Vec<String>,
+    stdin: &Secret<String>,
+) -> Result<Session> {
+    let stdin = Some(stdin.expose().as_bytes().to_vec());
+    let output = runner.exec(args, stdin, Vec::new()).await?;
+    handle_op_error("signin", &output)?;
+    let expiration_time = Utc::now() + Duration::minutes(SESSION_LIFETIME_MINUTES);
+    let token = Secret::new(String::from_utf8_lossy(&output.stdout).to_string());
+    #[cfg(feature = "session-cache")]
+    if let Some(cache) = &runner.session_cache {
+        cache.store(username, &token).await?;
+    }
+    Ok(Session {
+        token,
+        expiration_time,
+    })
+}
+
+//the session `op` would pick up from the environment, `OP_SESSION_<shorthand>`
+//of the `OP_ACCOUNT` account or the only one which is set.
+fn env_session(
+    env: &[(String, String)],
+    env_remove: &[String],
+) -> Result<(String, Secret<String>)> {
+    let mut vars: BTreeMap<String, String> = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .filter(|(key, _)| !env_remove.contains(key))
+        .collect();
+    vars.extend(env.iter().cloned());
+    let sessions: Vec<(&str, &String)> = vars
+        .iter()
+        .filter_map(|(key, token)| Some((key.strip_prefix("OP_SESSION_")?, token)))
+        .collect();
+    let account = vars.get("OP_ACCOUNT");
+    let found = match account {
+        Some(account) => sessions.iter().find(|(shorthand, _)| shorthand == account),
+        None if sessions.len() == 1 => sessions.first(),
+        None => None,
+    };
+    match (found, account) {
+        (Some((account, token)), _) => Ok((account.to_string(), Secret::new(token.to_string()))),
+        (None, Some(account)) => Err(Error::SessionError(format!(
+            "OP_SESSION_{} is not set",
+            account
+        ))),
+        (None, None) if sessions.is_empty() => Err(Error::SessionError(
+            "no OP_SESSION_<shorthand> variable is set".to_string(),
+        )),
+        (None, None) => Err(Error::SessionError(
+            "several OP_SESSION_<shorthand> variables are set, choose one with OP_ACCOUNT"
+                .to_string(),
+        )),
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct GetCmd {
     cmd: String,
-    session: String,
+    op: OpCLI,
 }
 
 impl sealed::FirstCmd for GetCmd {
@@ -85,8 +839,8 @@ impl sealed::FirstCmd for GetCmd {
         &self.cmd
     }
     #[doc(hidden)]
-    fn session(&self) -> &str {
-        &self.session
+    fn op(&self) -> &OpCLI {
+        &self.op
     }
 }
 
@@ -98,7 +852,7 @@ macro_rules! its_first_cmd {
         #[derive(Debug, Clone)]
         pub struct $FirstCmd {
             cmd: String,
-            session: String,
+            op: OpCLI,
         }
 
         impl sealed::FirstCmd for $FirstCmd {
@@ -107,15 +861,26 @@ macro_rules! its_first_cmd {
                 &self.cmd
             }
             #[doc(hidden)]
-            fn session(&self) -> &str {
-                &self.session
+            fn op(&self) -> &OpCLI {
+                &self.op
             }
         }
     )+
     };
 }
 
-its_first_cmd!(CreateCmd, ListCmd, DeleteCmd);
+its_first_cmd!(
+    CreateCmd,
+    ListCmd,
+    DeleteCmd,
+    EditCmd,
+    AddCmd,
+    RemoveCmd,
+    RestoreCmd,
+    ConfirmCmd,
+    SuspendCmd,
+    ReactivateCmd
+);
 
 //Maybe I can generic on some of second cmd's method, they seems like do same thing.
 //TODO
@@ -180,8 +945,74 @@ impl GetCmd {
             flags,
         }
     }
+
+    ///get a vault by its name or uuid.
+    pub fn vault(&self, vault: &str) -> GetVaultCmd {
+        let flags: Vec<String> = vec![vault.to_string()];
+        GetVaultCmd {
+            first: self.clone(),
+            cmd: "vault".to_string(),
+            flags,
+        }
+    }
+
+    ///get the details an item of a template starts with,
+    ///by the template's name or uuid.
+    pub fn template(&self, template: &str) -> GetTemplateCmd {
+        let flags: Vec<String> = vec![template.to_string()];
+        GetTemplateCmd {
+            first: self.clone(),
+            cmd: "template".to_string(),
+            flags,
+        }
+    }
+
+    ///get a group by its name or uuid.
+    pub fn group(&self, group: &str) -> GetGroupCmd {
+        let flags: Vec<String> = vec![group.to_string()];
+        GetGroupCmd {
+            first: self.clone(),
+            cmd: "group".to_string(),
+            flags,
+        }
+    }
+}
+impl GetDocumentCmd {
+    ///let `op` write the document to `path`, with its `--output` option.
+    pub async fn save_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut args = build_args(self);
+        args.push("--output".to_string());
+        args.push(path.as_ref().to_string_lossy().to_string());
+        self.first.op.run_command(args, None).await?;
+        Ok(())
+    }
+
+    ///pipe the document to `writer` as `op` prints it,
+    ///without buffering the whole document in memory.
+    pub async fn stream_to<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        self.first
+            .op
+            .run_command(build_args(self), Some(writer))
+            .await?;
+        Ok(())
+    }
 }
+
 impl CreateCmd {
+    ///create an item of `category`, set its fields with the returned builder.
+    pub fn item(&self, category: Category) -> CreateItemCmd {
+        CreateItemCmd {
+            first: self.clone(),
+            cmd: "item".to_string(),
+            category,
+            details: ItemDetails::default(),
+            flags: Vec::new(),
+        }
+    }
+
     pub fn document(&self, path: &str) -> CreateDocumentCmd {
         let flags: Vec<String> = vec![path.to_string()];
         CreateDocumentCmd {
@@ -190,6 +1021,73 @@ impl CreateCmd {
             flags,
         }
     }
+
+    pub fn vault(&self, name: &str) -> CreateVaultCmd {
+        let flags: Vec<String> = vec![name.to_string()];
+        CreateVaultCmd {
+            first: self.clone(),
+            cmd: "vault".to_string(),
+            flags,
+        }
+    }
+
+    ///invite a user, who is confirmed after accepting the invitation.
+    pub fn user(&self, email: &str, name: &str) -> CreateUserCmd {
+        let flags: Vec<String> = vec![email.to_string(), name.to_string()];
+        CreateUserCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            flags,
+        }
+    }
+
+    pub fn group(&self, name: &str) -> CreateGroupCmd {
+        let flags: Vec<String> = vec![name.to_string()];
+        CreateGroupCmd {
+            first: self.clone(),
+            cmd: "group".to_string(),
+            flags,
+        }
+    }
+}
+
+impl CreateUserCmd {
+    ///the language of the invitation email, e.g. `en`.
+    pub fn language(mut self, language: &str) -> Self {
+        self.flags.push("--language".to_string());
+        self.flags.push(language.to_string());
+        self
+    }
+}
+
+impl CreateGroupCmd {
+    pub fn description(mut self, description: &str) -> Self {
+        self.flags.push("--description".to_string());
+        self.flags.push(description.to_string());
+        self
+    }
+}
+
+impl CreateVaultCmd {
+    pub fn description(mut self, description: &str) -> Self {
+        self.flags.push("--description".to_string());
+        self.flags.push(description.to_string());
+        self
+    }
+
+    ///the vault's icon, e.g. `vault-door` or `buildings`.
+    pub fn icon(mut self, icon: &str) -> Self {
+        self.flags.push("--icon".to_string());
+        self.flags.push(icon.to_string());
+        self
+    }
+
+    ///whether administrators can manage the vault's access.
+    pub fn allow_admins_to_manage(mut self, allow: bool) -> Self {
+        self.flags.push("--allow-admins-to-manage".to_string());
+        self.flags.push(allow.to_string());
+        self
+    }
 }
 
 impl ListCmd {
@@ -219,26 +1117,910 @@ impl ListCmd {
             flags,
         }
     }
-}
 
-impl DeleteCmd {
-    pub fn item(&self) -> DeleteItemCmd {
+    pub fn vaults(&self) -> ListVaultsCmd {
         let flags: Vec<String> = Vec::new();
-        DeleteItemCmd {
+        ListVaultsCmd {
             first: self.clone(),
-            cmd: "item".to_string(),
+            cmd: "vaults".to_string(),
             flags,
         }
     }
 
-    pub fn document(&self, doc: &str) -> DeleteDocumentCmd {
-        let flags: Vec<String> = vec![doc.to_string()];
+    pub fn groups(&self) -> ListGroupsCmd {
+        let flags: Vec<String> = Vec::new();
+        ListGroupsCmd {
+            first: self.clone(),
+            cmd: "groups".to_string(),
+            flags,
+        }
+    }
+
+    pub fn templates(&self) -> ListTemplatesCmd {
+        let flags: Vec<String> = Vec::new();
+        ListTemplatesCmd {
+            first: self.clone(),
+            cmd: "templates".to_string(),
+            flags,
+        }
+    }
+
+    ///list the latest events of the activity log.
+    pub fn events(&self) -> ListEventsCmd {
+        let flags: Vec<String> = Vec::new();
+        ListEventsCmd {
+            first: self.clone(),
+            cmd: "events".to_string(),
+            flags,
+        }
+    }
+
+    ///list the groups and users who can access `vault`, with their permissions.
+    pub fn vault_permissions(&self, vault: &str) -> ListVaultPermissionsCmd {
+        ListVaultPermissionsCmd {
+            first: self.clone(),
+            vault: vault.to_string(),
+        }
+    }
+}
+
+///Runs `list groups --vault` and `list users --vault` for a vault.
+#[derive(Debug)]
+pub struct ListVaultPermissionsCmd {
+    first: ListCmd,
+    vault: String,
+}
+
+impl ListVaultPermissionsCmd {
+    pub async fn run(&self) -> Result<output::VaultPermissions> {
+        let op = &self.first.op;
+        let list = |cmd: &str| {
+            vec![
+                self.first.cmd.to_string(),
+                cmd.to_string(),
+                "--vault".to_string(),
+                self.vault.to_string(),
+            ]
+        };
+        let groups = op.run_command(list("groups"), None).await?;
+        let users = op.run_command(list("users"), None).await?;
+        Ok(output::VaultPermissions {
+            groups: serde_json::from_slice(&groups)?,
+            users: serde_json::from_slice(&users)?,
+        })
+    }
+}
+
+impl ListItemsCmd {
+    ///list the items in the trash too.
+    pub fn include_trash(mut self) -> Self {
+        self.flags.push("--include-trash".to_string());
+        self
+    }
+
+    ///list the archived items too.
+    pub fn include_archive(mut self) -> Self {
+        self.flags.push("--include-archive".to_string());
+        self
+    }
+}
+
+impl DeleteItemCmd {
+    ///move the item to the archive instead of the trash.
+    pub fn archive(mut self) -> Self {
+        self.flags.push("--archive".to_string());
+        self
+    }
+}
+
+impl RestoreCmd {
+    ///restore an item from the trash or the archive.
+    pub fn item(&self, item: &str) -> RestoreItemCmd {
+        let flags: Vec<String> = vec![item.to_string()];
+        RestoreItemCmd {
+            first: self.clone(),
+            cmd: "item".to_string(),
+            flags,
+        }
+    }
+}
+
+impl ListEventsCmd {
+    ///list the events after `eid`, or before it with `older`.
+    pub fn event_id(mut self, eid: u64) -> Self {
+        self.flags.push("--eventid".to_string());
+        self.flags.push(eid.to_string());
+        self
+    }
+
+    pub fn older(mut self) -> Self {
+        self.flags.push("--older".to_string());
+        self
+    }
+
+    ///walk backwards through the whole log, a page at a time. It starts
+    ///at the newest event, or before `event_id` if it is set.
+    pub fn stream(&self) -> impl Stream<Item = Result<output::Event>> + Send + 'static {
+        let op = self.first.op.clone();
+        let list = self.first.cmd.to_string();
+        let events = self.cmd.to_string();
+        let start = self
+            .flags
+            .iter()
+            .position(|flag| flag == "--eventid")
+            .and_then(|i| self.flags.get(i + 1))
+            .and_then(|eid| eid.parse::<u64>().ok());
+        //the state is the event the next page is older than,
+        //None once the oldest event was reached.
+        let pages = stream::try_unfold(Some(start), move |cursor| {
+            let op = op.clone();
+            let mut args = vec![list.to_string(), events.to_string()];
+            async move {
+                let cursor: Option<u64> = match cursor {
+                    Some(cursor) => cursor,
+                    None => return Result::Ok(None),
+                };
+                if let Some(eid) = cursor {
+                    args.push("--eventid".to_string());
+                    args.push(eid.to_string());
+                    args.push("--older".to_string());
+                }
+                let out = op.run_command(args, None).await?;
+                let page = output::ListEvents::from_output(&out)?;
+                let next = match (page.iter().map(|event| event.eid).min(), cursor) {
+                    (Some(oldest), Some(eid)) if oldest >= eid => None,
+                    (Some(oldest), _) => Some(Some(oldest)),
+                    (None, _) => None,
+                };
+                Result::Ok(Some((page, next)))
+            }
+        });
+        pages
+            .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+            .try_flatten()
+    }
+}
+
+impl ListUsersCmd {
+    ///only list the members of `group`, with their role.
+    pub fn group(mut self, group: &str) -> Self {
+        self.flags.push("--group".to_string());
+        self.flags.push(group.to_string());
+        self
+    }
+
+    ///only list the users who have access to `vault`.
+    pub fn vault(mut self, vault: &str) -> Self {
+        self.flags.push("--vault".to_string());
+        self.flags.push(vault.to_string());
+        self
+    }
+}
+
+impl ListGroupsCmd {
+    ///only list the groups which have access to `vault`.
+    pub fn vault(mut self, vault: &str) -> Self {
+        self.flags.push("--vault".to_string());
+        self.flags.push(vault.to_string());
+        self
+    }
+
+    ///only list the groups `user` belongs to, with the user's role.
+    pub fn user(mut self, user: &str) -> Self {
+        self.flags.push("--user".to_string());
+        self.flags.push(user.to_string());
+        self
+    }
+}
+
+impl DeleteCmd {
+    ///move an item to the trash, or to the archive with `archive`.
+    pub fn item(&self, item: &str) -> DeleteItemCmd {
+        let flags: Vec<String> = vec![item.to_string()];
+        DeleteItemCmd {
+            first: self.clone(),
+            cmd: "item".to_string(),
+            flags,
+        }
+    }
+
+    ///empty the trash of `vault`, its items can not be restored afterwards.
+    pub fn trash(&self, vault: &str) -> DeleteTrashCmd {
+        let flags: Vec<String> = vec![vault.to_string()];
+        DeleteTrashCmd {
+            first: self.clone(),
+            cmd: "trash".to_string(),
+            flags,
+        }
+    }
+
+    pub fn document(&self, doc: &str) -> DeleteDocumentCmd {
+        let flags: Vec<String> = vec![doc.to_string()];
         DeleteDocumentCmd {
             first: self.clone(),
             cmd: "document".to_string(),
             flags,
         }
     }
+
+    pub fn vault(&self, vault: &str) -> DeleteVaultCmd {
+        let flags: Vec<String> = vec![vault.to_string()];
+        DeleteVaultCmd {
+            first: self.clone(),
+            cmd: "vault".to_string(),
+            flags,
+        }
+    }
+
+    pub fn user(&self, user: &str) -> DeleteUserCmd {
+        let flags: Vec<String> = vec![user.to_string()];
+        DeleteUserCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            flags,
+        }
+    }
+
+    pub fn group(&self, group: &str) -> DeleteGroupCmd {
+        let flags: Vec<String> = vec![group.to_string()];
+        DeleteGroupCmd {
+            first: self.clone(),
+            cmd: "group".to_string(),
+            flags,
+        }
+    }
+}
+
+//`op confirm`, `op suspend` and `op reactivate` have no subcommand,
+//the user takes its place.
+impl ConfirmCmd {
+    ///confirm a user who accepted the invitation.
+    pub fn user(&self, user: &str) -> ConfirmUserCmd {
+        ConfirmUserCmd {
+            first: self.clone(),
+            cmd: user.to_string(),
+            flags: Vec::new(),
+        }
+    }
+
+    ///confirm every user who accepted the invitation.
+    pub fn all(&self) -> ConfirmUserCmd {
+        ConfirmUserCmd {
+            first: self.clone(),
+            cmd: "--all".to_string(),
+            flags: Vec::new(),
+        }
+    }
+}
+
+impl SuspendCmd {
+    pub fn user(&self, user: &str) -> SuspendUserCmd {
+        SuspendUserCmd {
+            first: self.clone(),
+            cmd: user.to_string(),
+            flags: Vec::new(),
+        }
+    }
+}
+
+impl SuspendUserCmd {
+    ///sign the user out of their devices after `delay`.
+    pub fn deauthorize_devices_after(mut self, delay: std::time::Duration) -> Self {
+        self.flags.push("--deauthorize-devices-after".to_string());
+        self.flags.push(format!("{}s", delay.as_secs()));
+        self
+    }
+}
+
+impl ReactivateCmd {
+    pub fn user(&self, user: &str) -> ReactivateUserCmd {
+        ReactivateUserCmd {
+            first: self.clone(),
+            cmd: user.to_string(),
+            flags: Vec::new(),
+        }
+    }
+}
+
+impl AddCmd {
+    ///add `user` to `group`, as a member unless another role is given.
+    pub fn user_to_group(&self, user: &str, group: &str) -> AddUserToGroupCmd {
+        let flags: Vec<String> = vec![user.to_string(), group.to_string()];
+        AddUserToGroupCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            flags,
+        }
+    }
+
+    ///give `group` access to `vault`, with `permissions` if they are set.
+    pub fn group_to_vault(&self, group: &str, vault: &str) -> AddGroupToVaultCmd {
+        let flags: Vec<String> = vec![group.to_string(), vault.to_string()];
+        AddGroupToVaultCmd {
+            first: self.clone(),
+            cmd: "group".to_string(),
+            flags,
+        }
+    }
+
+    ///give `user` access to `vault`, with `permissions` if they are set.
+    ///`op` 2 needs the permissions, it fails with `Error::MissingArgument`
+    ///without them.
+    pub fn user_to_vault(&self, user: &str, vault: &str) -> AddUserToVaultCmd {
+        AddUserToVaultCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            user: user.to_string(),
+            vault: vault.to_string(),
+            flags: Vec::new(),
+        }
+    }
+}
+
+impl AddUserToGroupCmd {
+    pub fn role(mut self, role: output::Role) -> Self {
+        self.flags.push("--role".to_string());
+        self.flags.push(role.as_str().to_string());
+        self
+    }
+}
+
+impl RemoveCmd {
+    pub fn user_from_group(&self, user: &str, group: &str) -> RemoveUserFromGroupCmd {
+        let flags: Vec<String> = vec![user.to_string(), group.to_string()];
+        RemoveUserFromGroupCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            flags,
+        }
+    }
+
+    ///take `group`'s access to `vault` away, or only `permissions` if they are set.
+    pub fn group_from_vault(&self, group: &str, vault: &str) -> RemoveGroupFromVaultCmd {
+        let flags: Vec<String> = vec![group.to_string(), vault.to_string()];
+        RemoveGroupFromVaultCmd {
+            first: self.clone(),
+            cmd: "group".to_string(),
+            flags,
+        }
+    }
+
+    ///take `user`'s access to `vault` away, or only `permissions` if they are set.
+    ///`op` 2 needs the permissions, it fails with `Error::MissingArgument`
+    ///without them.
+    pub fn user_from_vault(&self, user: &str, vault: &str) -> RemoveUserFromVaultCmd {
+        RemoveUserFromVaultCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            user: user.to_string(),
+            vault: vault.to_string(),
+            flags: Vec::new(),
+        }
+    }
+}
+
+//this macro adds a `permissions` method to the commands which grant or
+//revoke access to a vault.
+macro_rules! impl_permissions {
+    ($($Cmd:ident),+ $(,)?) => {
+        $(
+            impl $Cmd {
+                pub fn permissions(mut self, permissions: &[output::Permission]) -> Self {
+                    let permissions: Vec<&str> =
+                        permissions.iter().map(output::Permission::as_str).collect();
+                    self.flags.push("--permissions".to_string());
+                    self.flags.push(permissions.join(","));
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_permissions!(
+    AddGroupToVaultCmd,
+    AddUserToVaultCmd,
+    RemoveGroupFromVaultCmd,
+    RemoveUserFromVaultCmd
+);
+
+impl EditCmd {
+    ///edit the item `item`, better given by uuid since the title may change.
+    pub fn item(&self, item: &str) -> EditItemCmd {
+        EditItemCmd {
+            first: self.clone(),
+            cmd: "item".to_string(),
+            item: item.to_string(),
+            vault: None,
+            assignments: Vec::new(),
+            flags: Vec::new(),
+            renamed: false,
+            relocation: None,
+        }
+    }
+
+    pub fn vault(&self, vault: &str) -> EditVaultCmd {
+        EditVaultCmd {
+            first: self.clone(),
+            cmd: "vault".to_string(),
+            vault: vault.to_string(),
+            name: None,
+            flags: Vec::new(),
+        }
+    }
+
+    pub fn user(&self, user: &str) -> EditUserCmd {
+        EditUserCmd {
+            first: self.clone(),
+            cmd: "user".to_string(),
+            user: user.to_string(),
+            flags: Vec::new(),
+        }
+    }
+
+    pub fn group(&self, group: &str) -> EditGroupCmd {
+        EditGroupCmd {
+            first: self.clone(),
+            cmd: "group".to_string(),
+            group: group.to_string(),
+            name: None,
+            flags: Vec::new(),
+        }
+    }
+
+    ///replace the contents of the document `document`, its uuid and
+    ///history are kept. Give the new contents with `file`, `bytes` or `reader`.
+    pub fn document(&self, document: &str) -> EditDocumentCmd {
+        EditDocumentCmd {
+            first: self.clone(),
+            cmd: "document".to_string(),
+            document: document.to_string(),
+            content: None,
+            title: None,
+            flags: Vec::new(),
+        }
+    }
+}
+
+///`op edit item`. Running it returns the item as it is after the edit.
+#[derive(Debug)]
+pub struct EditItemCmd {
+    first: EditCmd,
+    cmd: String,
+    item: String,
+    vault: Option<String>,
+    assignments: Vec<Assignment>,
+    flags: Vec<String>,
+    renamed: bool,
+    relocation: Option<Relocation>,
+}
+
+//where an edited item goes afterwards.
+#[derive(Debug)]
+enum Relocation {
+    Move(String),
+    CopyAndTrash(String),
+}
+
+impl EditItemCmd {
+    ///look the item up in `vault`.
+    pub fn vault(mut self, vault: &str) -> Self {
+        self.vault = Some(vault.to_string());
+        self
+    }
+
+    ///set a field, e.g. `Assignment::new("username", "octocat")`. The
+    ///value is given to `op` as an argument, which others on the host can
+    ///see, so prefer `generate_password` to set a new password.
+    pub fn assign(mut self, assignment: Assignment) -> Self {
+        self.assignments.push(assignment);
+        self
+    }
+
+    ///replace the password with a new one made by `op` from `recipe`.
+    pub fn generate_password(mut self, recipe: PasswordRecipe) -> Self {
+        let recipe = recipe.to_arg();
+        if recipe.is_empty() {
+            self.flags.push("--generate-password".to_string());
+        } else {
+            self.flags.push(format!("--generate-password={}", recipe));
+        }
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.renamed = true;
+        self.flags.push("--title".to_string());
+        self.flags.push(title.to_string());
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.flags.push("--url".to_string());
+        self.flags.push(url.to_string());
+        self
+    }
+
+    ///replace the item's tags.
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.flags.push("--tags".to_string());
+        self.flags.push(tags.join(","));
+        self
+    }
+
+    ///move the item to `vault` with `op item move`, the moved item has a
+    ///new uuid. Needs `op` 2, see `copy_to_vault_and_trash_original` for v1.
+    pub fn move_to(mut self, vault: &str) -> Self {
+        self.relocation = Some(Relocation::Move(vault.to_string()));
+        self
+    }
+
+    ///create a copy of the item in `vault` and delete the original, for the
+    ///v1 cli which can not move items. Only the details and the title, url
+    ///and tags are copied, the attachments and the history are lost with
+    ///the original and the copy has a new uuid.
+    pub fn copy_to_vault_and_trash_original(mut self, vault: &str) -> Self {
+        self.relocation = Some(Relocation::CopyAndTrash(vault.to_string()));
+        self
+    }
+
+    pub async fn run(&self) -> Result<output::GetItem> {
+        let op = &self.first.op;
+        //fail before anything is edited when the item can not be relocated.
+        let relocation = match &self.relocation {
+            Some(Relocation::Move(vault)) => Some(self.move_args(&self.item, vault)),
+            Some(Relocation::CopyAndTrash(_)) => {
+                Some(vec!["create".to_string(), "item".to_string()])
+            }
+            None => None,
+        };
+        if let Some(args) = &relocation {
+            grammar::check(args, op.cli_version())?;
+        }
+        //a new title may not find the item anymore, so it is known by uuid.
+        let mut original = match self.renamed || relocation.is_some() {
+            true => Some(self.get_item(&self.item).await?),
+            false => None,
+        };
+        let item = match &original {
+            Some(original) => original.uuid.to_string(),
+            None => self.item.to_string(),
+        };
+        if !self.assignments.is_empty() || !self.flags.is_empty() {
+            op.run_command(self.edit_args(&item), None).await?;
+            original = None;
+        }
+        match &self.relocation {
+            Some(Relocation::Move(vault)) => {
+                let out = op.run_command(self.move_args(&item, vault), None).await?;
+                output::GetItem::from_output(&out)
+            }
+            Some(Relocation::CopyAndTrash(vault)) => {
+                let original = match original {
+                    Some(original) => original,
+                    None => self.get_item(&item).await?,
+                };
+                let created = self.copy_to(&original, vault).await?;
+                op.run_command(
+                    vec!["delete".to_string(), "item".to_string(), original.uuid],
+                    None,
+                )
+                .await?;
+                op.get().item(&created.uuid).run().await
+            }
+            None => self.get_item(&item).await,
+        }
+    }
+
+    fn move_args(&self, item: &str, vault: &str) -> Vec<String> {
+        let mut args = vec![
+            "move".to_string(),
+            "item".to_string(),
+            item.to_string(),
+            "--destination-vault".to_string(),
+            vault.to_string(),
+        ];
+        if let Some(current) = &self.vault {
+            args.push("--vault".to_string());
+            args.push(current.to_string());
+        }
+        args
+    }
+
+    fn edit_args(&self, item: &str) -> Vec<String> {
+        let mut args = vec![
+            self.first.cmd.to_string(),
+            self.cmd.to_string(),
+            item.to_string(),
+        ];
+        args.extend(
+            self.assignments
+                .iter()
+                .map(|assignment| assignment.expose().to_string()),
+        );
+        args.extend(self.flags.iter().cloned());
+        if let Some(vault) = &self.vault {
+            args.push("--vault".to_string());
+            args.push(vault.to_string());
+        }
+        args
+    }
+
+    async fn get_item(&self, item: &str) -> Result<output::GetItem> {
+        let mut cmd = self.first.op.get().item(item);
+        if let Some(vault) = &self.vault {
+            cmd.flags.push("--vault".to_string());
+            cmd.flags.push(vault.to_string());
+        }
+        cmd.run().await
+    }
+
+    //create a copy of `item` in `vault` with the same details and overview.
+    async fn copy_to(&self, item: &output::GetItem, vault: &str) -> Result<output::CreateItem> {
+        let category = Category::from_template_uuid(&item.template_uuid)
+            .map(|category| category.name().to_string())
+            .unwrap_or_else(|| item.template_uuid.to_string());
+        let mut args = vec![
+            "create".to_string(),
+            "item".to_string(),
+            category,
+            "--vault".to_string(),
+            vault.to_string(),
+        ];
+        if let Some(title) = item.overview["title"].as_str() {
+            args.push("--title".to_string());
+            args.push(title.to_string());
+        }
+        if let Some(url) = item.overview["url"].as_str() {
+            args.push("--url".to_string());
+            args.push(url.to_string());
+        }
+        if let Some(tags) = item.overview["tags"].as_array() {
+            let tags: Vec<&str> = tags.iter().filter_map(|tag| tag.as_str()).collect();
+            args.push("--tags".to_string());
+            args.push(tags.join(","));
+        }
+        create_item(&self.first.op, args, &item.details).await
+    }
+}
+
+///`op edit vault`. Running it returns the vault as it is after the edit.
+#[derive(Debug)]
+pub struct EditVaultCmd {
+    first: EditCmd,
+    cmd: String,
+    vault: String,
+    name: Option<String>,
+    flags: Vec<String>,
+}
+
+impl EditVaultCmd {
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.flags.push("--description".to_string());
+        self.flags.push(description.to_string());
+        self
+    }
+
+    pub fn icon(mut self, icon: &str) -> Self {
+        self.flags.push("--icon".to_string());
+        self.flags.push(icon.to_string());
+        self
+    }
+
+    pub fn travel_mode(mut self, on: bool) -> Self {
+        self.flags.push("--travel-mode".to_string());
+        self.flags.push(if on { "on" } else { "off" }.to_string());
+        self
+    }
+
+    pub async fn run(&self) -> Result<output::Vault> {
+        let op = &self.first.op;
+        //a new name may not find the vault anymore, so it is known by uuid.
+        let vault = match &self.name {
+            Some(_) => op.get().vault(&self.vault).run().await?.uuid,
+            None => self.vault.to_string(),
+        };
+        let mut args = vec![
+            self.first.cmd.to_string(),
+            self.cmd.to_string(),
+            vault.to_string(),
+        ];
+        if let Some(name) = &self.name {
+            args.push("--name".to_string());
+            args.push(name.to_string());
+        }
+        args.extend(self.flags.iter().cloned());
+        op.run_command(args, None).await?;
+        op.get().vault(&vault).run().await
+    }
+}
+
+///`op edit document`. Running it returns the document's item as it is
+///after the edit, with the new `item_version`.
+#[derive(Debug)]
+pub struct EditDocumentCmd {
+    first: EditCmd,
+    cmd: String,
+    document: String,
+    content: Option<DocumentContent>,
+    title: Option<String>,
+    flags: Vec<String>,
+}
+
+//where the new contents of a document come from.
+enum DocumentContent {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+    Reader(Box<dyn AsyncRead + Unpin + Send>),
+}
+
+impl fmt::Debug for DocumentContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Self::Reader(_) => f.write_str("Reader"),
+        }
+    }
+}
+
+impl EditDocumentCmd {
+    ///upload the file at `path`, `op` reads it itself.
+    pub fn file(mut self, path: impl AsRef<Path>) -> Self {
+        self.content = Some(DocumentContent::File(path.as_ref().to_path_buf()));
+        self
+    }
+
+    pub fn bytes(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.content = Some(DocumentContent::Bytes(bytes.into()));
+        self
+    }
+
+    ///upload what `reader` yields. `op` reads its stdin to the end before
+    ///uploading, so the contents are read into memory first.
+    pub fn reader<R>(mut self, reader: R) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        self.content = Some(DocumentContent::Reader(Box::new(reader)));
+        self
+    }
+
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.flags.push("--file-name".to_string());
+        self.flags.push(file_name.to_string());
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub async fn run(self) -> Result<output::GetItem> {
+        let op = &self.first.op;
+        //a new title may not find the document anymore, so it is known by uuid.
+        let document = match &self.title {
+            Some(_) => op.get().item(&self.document).run().await?.uuid,
+            None => self.document.to_string(),
+        };
+        let mut args = vec![
+            self.first.cmd.to_string(),
+            self.cmd.to_string(),
+            document.to_string(),
+        ];
+        //contents which are not in a file are given on stdin, as `-`.
+        let stdin = match self.content {
+            Some(DocumentContent::File(path)) => {
+                args.push(path.to_string_lossy().to_string());
+                None
+            }
+            Some(DocumentContent::Bytes(bytes)) => Some(bytes),
+            Some(DocumentContent::Reader(mut reader)) => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes).await?;
+                Some(bytes)
+            }
+            None => None,
+        };
+        if stdin.is_some() {
+            args.push("-".to_string());
+        }
+        if let Some(title) = &self.title {
+            args.push("--title".to_string());
+            args.push(title.to_string());
+        }
+        args.extend(self.flags.iter().cloned());
+        match stdin {
+            Some(stdin) => op.run_command_with_stdin(args, stdin).await?,
+            None => op.run_command(args, None).await?,
+        };
+        op.get().item(&document).run().await
+    }
+}
+
+///`op edit user`. Running it returns the user as it is after the edit.
+#[derive(Debug)]
+pub struct EditUserCmd {
+    first: EditCmd,
+    cmd: String,
+    user: String,
+    flags: Vec<String>,
+}
+
+impl EditUserCmd {
+    pub fn name(mut self, name: &str) -> Self {
+        self.flags.push("--name".to_string());
+        self.flags.push(name.to_string());
+        self
+    }
+
+    pub fn travel_mode(mut self, on: bool) -> Self {
+        self.flags.push("--travel-mode".to_string());
+        self.flags.push(if on { "on" } else { "off" }.to_string());
+        self
+    }
+
+    pub async fn run(&self) -> Result<output::GetUser> {
+        let op = &self.first.op;
+        let mut args = vec![
+            self.first.cmd.to_string(),
+            self.cmd.to_string(),
+            self.user.to_string(),
+        ];
+        args.extend(self.flags.iter().cloned());
+        op.run_command(args, None).await?;
+        op.get().user(&self.user).run().await
+    }
+}
+
+///`op edit group`. Running it returns the group as it is after the edit.
+#[derive(Debug)]
+pub struct EditGroupCmd {
+    first: EditCmd,
+    cmd: String,
+    group: String,
+    name: Option<String>,
+    flags: Vec<String>,
+}
+
+impl EditGroupCmd {
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.flags.push("--description".to_string());
+        self.flags.push(description.to_string());
+        self
+    }
+
+    pub async fn run(&self) -> Result<output::Group> {
+        let op = &self.first.op;
+        //a new name may not find the group anymore, so it is known by uuid.
+        let group = match &self.name {
+            Some(_) => op.get().group(&self.group).run().await?.uuid,
+            None => self.group.to_string(),
+        };
+        let mut args = vec![
+            self.first.cmd.to_string(),
+            self.cmd.to_string(),
+            group.to_string(),
+        ];
+        if let Some(name) = &self.name {
+            args.push("--name".to_string());
+            args.push(name.to_string());
+        }
+        args.extend(self.flags.iter().cloned());
+        op.run_command(args, None).await?;
+        op.get().group(&group).run().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -253,27 +2035,27 @@ pub trait SecondCmdExt: SecondCmd {
     }
 
     async fn run(&self) -> Result<Self::Output> {
-        let mut args: Vec<String> = vec![
-            self.first().cmd().to_string(),
-            self.cmd().to_string(),
-            "--session".to_string(),
-            self.first().session().trim().to_string(),
-        ];
-        if !self.flags().is_empty() {
-            self.flags()
-                .into_iter()
-                .for_each(|flag| args.push(flag.to_string()))
-        }
-        let out_str: &str = &exec_command(args).await?;
-        if out_str.len() == 0 {
-            return Ok(serde_json::from_str("{\"field\":\"ok\"}")?);
-        }
-        Ok(serde_json::from_str(out_str)?)
+        let out = self
+            .first()
+            .op()
+            .run_command(build_args(self), None)
+            .await?;
+        Self::Output::from_output(&out)
     }
 }
 
 impl<T: SecondCmd> SecondCmdExt for T {}
 
+fn build_args<T: SecondCmd + ?Sized>(cmd: &T) -> Vec<String> {
+    let mut args: Vec<String> = vec![cmd.first().cmd().to_string(), cmd.cmd().to_string()];
+    if !cmd.flags().is_empty() {
+        cmd.flags()
+            .into_iter()
+            .for_each(|flag| args.push(flag.to_string()))
+    }
+    args
+}
+
 #[derive(Debug)]
 pub struct AccountCmd {
     first: GetCmd,
@@ -302,6 +2084,185 @@ impl SecondCmd for AccountCmd {
     }
 }
 
+///`op create item <category> --template <file>`, `op item create` for `op` 2.
+///The details are built from the username, password, notes, sections and
+///fields set here, and given to `op` in a file only the owner can read,
+///which needs `op` 1.8 or later.
+#[derive(Debug)]
+pub struct CreateItemCmd {
+    first: CreateCmd,
+    cmd: String,
+    category: Category,
+    details: ItemDetails,
+    flags: Vec<String>,
+}
+
+impl CreateItemCmd {
+    pub fn title(mut self, title: &str) -> Self {
+        self.flags.push("--title".to_string());
+        self.flags.push(title.to_string());
+        self
+    }
+
+    ///the vault to create the item in, the default is the Private vault.
+    pub fn vault(mut self, vault: &str) -> Self {
+        self.flags.push("--vault".to_string());
+        self.flags.push(vault.to_string());
+        self
+    }
+
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.flags.push("--tags".to_string());
+        self.flags.push(tags.join(","));
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.flags.push("--url".to_string());
+        self.flags.push(url.to_string());
+        self
+    }
+
+    ///start from the details of a template, fetched with `get().template(..)`.
+    ///The username, password, notes, sections and fields set here are
+    ///applied on top of it.
+    pub fn template(mut self, template: output::GetTemplate) -> Self {
+        self.details.template = Some(template);
+        self
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.details.username = Some(username.to_string());
+        self
+    }
+
+    pub fn password(mut self, password: impl Into<Secret<String>>) -> Self {
+        self.details.password = Some(password.into());
+        self
+    }
+
+    pub fn notes(mut self, notes: &str) -> Self {
+        self.details.notes = Some(notes.to_string());
+        self
+    }
+
+    pub fn section(mut self, section: Section) -> Self {
+        self.details.sections.push(section);
+        self
+    }
+
+    ///add a custom field which does not belong to a titled section.
+    pub fn field(mut self, field: Field) -> Self {
+        self.details.fields.push(field);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SecondCmd for CreateItemCmd {
+    type Output = output::CreateItem;
+    type First = CreateCmd;
+
+    #[doc(hidden)]
+    fn first(&self) -> &CreateCmd {
+        &self.first
+    }
+
+    #[doc(hidden)]
+    fn cmd(&self) -> &str {
+        &self.cmd
+    }
+
+    //the details are not an argument, `run` gives them in a file.
+    #[doc(hidden)]
+    fn flags(&self) -> Vec<String> {
+        let mut flags = vec![self.category.name().to_string()];
+        flags.extend(self.flags.iter().cloned());
+        flags
+    }
+}
+
+impl CreateItemCmd {
+    pub async fn run(&self) -> Result<output::CreateItem> {
+        create_item(&self.first.op, build_args(self), &self.details.to_json()).await
+    }
+
+    pub fn add_flag(&mut self, flags: &[&str]) -> &Self {
+        <Self as SecondCmdExt>::add_flag(self, flags)
+    }
+}
+
+//`add user` and `remove user` name a group or a vault, these commands
+//name a vault. They are translated with `Grammar::vault_access_args`.
+macro_rules! its_vault_access_cmd {
+    ($(($FirstCmd:ident,$SecondCmd:ident,$Output:ident)),+ $(,)?) => {
+        $(#[derive(Debug)]
+        pub struct $SecondCmd {
+            first: $FirstCmd,
+            cmd: String,
+            user: String,
+            vault: String,
+            flags: Vec<String>,
+        }
+
+        impl SecondCmd for $SecondCmd {
+            type Output = output::$Output;
+            type First = $FirstCmd;
+            #[doc(hidden)]
+            fn first(&self) -> &$FirstCmd {
+                &self.first
+            }
+            #[doc(hidden)]
+            fn cmd(&self) -> &str {
+                &self.cmd
+            }
+            #[doc(hidden)]
+            fn flags(&self) -> Vec<String> {
+                let mut flags = vec![self.user.to_string(), self.vault.to_string()];
+                flags.extend(self.flags.iter().cloned());
+                flags
+            }
+        }
+
+        impl $SecondCmd {
+            pub async fn run(&self) -> Result<output::$Output> {
+                let out = self
+                    .first
+                    .op
+                    .run_command_with(build_args(self), None, None, Grammar::vault_access_args)
+                    .await?;
+                output::$Output::from_output(&out)
+            }
+
+            pub fn add_flag(&mut self, flags: &[&str]) -> &Self {
+                <Self as SecondCmdExt>::add_flag(self, flags)
+            }
+        })+
+    };
+}
+
+its_vault_access_cmd!(
+    (AddCmd, AddUserToVaultCmd, Added),
+    (RemoveCmd, RemoveUserFromVaultCmd, Removed)
+);
+
+//run `create item <category> ..` with the item's details in a
+//`DetailsFile`, they never appear in the arguments.
+async fn create_item(
+    op: &OpCLI,
+    mut args: Vec<String>,
+    details: &serde_json::Value,
+) -> Result<output::CreateItem> {
+    let file = match op.runner.grammar() {
+        Grammar::V1 => item::DetailsFile::create(details).await?,
+        Grammar::V2 => item::DetailsFile::create(&item::v2_template(details)).await?,
+    };
+    let template = file.path().to_string_lossy().to_string();
+    args.splice(3..3, ["--template".to_string(), template]);
+    let out = op.run_command(args, None).await?;
+    output::CreateItem::from_output(&out)
+}
+
 //This macro repeat above codes. To create a new second cmd struct
 //and implement SecondCmd trait for it.
 macro_rules! its_second_cmd {
@@ -335,55 +2296,49 @@ macro_rules! its_second_cmd {
 
 its_second_cmd!(
     (GetCmd, ItemLiteCmd, ItemLite),
-    (GetCmd, GetDocumentCmd, Value),
-    (GetCmd, GetTotpCmd, Value),
+    (GetCmd, GetDocumentCmd, Document),
+    (GetCmd, GetTotpCmd, Totp),
     (GetCmd, GetItemCmd, GetItem),
     (GetCmd, GetUserCmd, GetUser),
     (CreateCmd, CreateDocumentCmd, CreateDocument),
     (ListCmd, ListDocumentsCmd, ListDocuments),
     (ListCmd, ListItemsCmd, ListItems),
     (ListCmd, ListUsersCmd, ListUsers),
-    (DeleteCmd, DeleteItemCmd, DeleteItem),
-    (DeleteCmd, DeleteDocumentCmd, DeleteDocument)
+    (GetCmd, GetVaultCmd, Vault),
+    (CreateCmd, CreateVaultCmd, Vault),
+    (ListCmd, ListVaultsCmd, ListVaults),
+    (DeleteCmd, DeleteItemCmd, Deleted),
+    (DeleteCmd, DeleteDocumentCmd, Deleted),
+    (DeleteCmd, DeleteVaultCmd, Deleted),
+    (GetCmd, GetGroupCmd, Group),
+    (CreateCmd, CreateGroupCmd, Group),
+    (ListCmd, ListGroupsCmd, ListGroups),
+    (DeleteCmd, DeleteGroupCmd, Deleted),
+    (AddCmd, AddUserToGroupCmd, Added),
+    (RemoveCmd, RemoveUserFromGroupCmd, Removed),
+    (CreateCmd, CreateUserCmd, CreateUser),
+    (DeleteCmd, DeleteUserCmd, Deleted),
+    (ConfirmCmd, ConfirmUserCmd, Confirmed),
+    (SuspendCmd, SuspendUserCmd, Suspended),
+    (ReactivateCmd, ReactivateUserCmd, Reactivated),
+    (AddCmd, AddGroupToVaultCmd, Added),
+    (RemoveCmd, RemoveGroupFromVaultCmd, Removed),
+    (ListCmd, ListEventsCmd, ListEvents),
+    (GetCmd, GetTemplateCmd, GetTemplate),
+    (ListCmd, ListTemplatesCmd, ListTemplates),
+    (DeleteCmd, DeleteTrashCmd, Deleted),
+    (RestoreCmd, RestoreItemCmd, Restored)
 );
 
+//turn an invocation which exited with a non-zero status into an
+//`Error::OPCommandError`, its kind is classified from stderr.
 #[inline]
-async fn exec_command(args: Vec<String>) -> Result<String> {
-    let child = Command::new("op")
-        .args(args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-    let output = child.wait_with_output().await?;
-    handle_op_exec_error(String::from_utf8_lossy(&output.stderr).to_string()).await?;
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
-
-#[inline]
-async fn handle_op_signin_error(std_err: String) -> std::result::Result<(), Error> {
-    match std_err.trim() {
-        err if err.contains("401") => Err(Error::OPSignInError("Wrong password".to_string())),
-        err if err.contains("Account not found") => Err(Error::OPSignInError(
-            "Account does not exist,may be you should firstly setup 1password-cli.".to_string(),
-        )),
-        _ => Ok(()),
-    }
-}
-
-#[inline]
-async fn handle_op_exec_error(std_err: String) -> std::result::Result<(), Error> {
-    match std_err.trim() {
-        err if err.contains("doesn't seem to be an item") => {
-            Err(Error::ItemQueryError("Item not founded".to_string()))
-        }
-        err if err.contains("Invalid session token") => {
-            Err(Error::ItemQueryError("In valid session token".to_string()))
-        }
-        err if err.contains("More than one item matches") => Err(Error::ItemQueryError(
-            "More than one item matches,Please specify one by uuid".to_string(),
-        )),
-        _ => Ok(()),
+fn handle_op_error(command: &str, output: &ExecOutput) -> Result<()> {
+    if output.is_success() {
+        return Ok(());
     }
+    let std_err = String::from_utf8_lossy(&output.stderr);
+    Err(Error::op_command(command, output.status, &std_err))
 }
 
 //why I need this: Cause of SecondCmdExt need explicit scope in.
@@ -411,25 +2366,50 @@ impl_casting_method!(
     GetDocumentCmd,
     GetTotpCmd,
     GetItemCmd,
+    GetUserCmd,
     CreateDocumentCmd,
     ListDocumentsCmd,
     ListItemsCmd,
+    ListUsersCmd,
+    GetVaultCmd,
+    CreateVaultCmd,
+    ListVaultsCmd,
+    DeleteItemCmd,
+    DeleteDocumentCmd,
+    DeleteVaultCmd,
+    GetGroupCmd,
+    CreateGroupCmd,
+    ListGroupsCmd,
+    DeleteGroupCmd,
+    AddUserToGroupCmd,
+    RemoveUserFromGroupCmd,
+    CreateUserCmd,
+    DeleteUserCmd,
+    ConfirmUserCmd,
+    SuspendUserCmd,
+    ReactivateUserCmd,
+    AddGroupToVaultCmd,
+    RemoveGroupFromVaultCmd,
+    ListEventsCmd,
+    GetTemplateCmd,
+    ListTemplatesCmd,
+    DeleteTrashCmd,
+    RestoreItemCmd,
     AccountCmd
 );
 
 mod sealed {
-    use serde::de::DeserializeOwned;
 
     pub trait FirstCmd {
         #[doc(hidden)]
         fn cmd(&self) -> &str;
         #[doc(hidden)]
-        fn session(&self) -> &str;
+        fn op(&self) -> &crate::OpCLI;
     }
 
     #[async_trait::async_trait]
     pub trait SecondCmd {
-        type Output: DeserializeOwned;
+        type Output: FromOutput;
         type First: FirstCmd + Clone;
 
         #[doc(hidden)]
@@ -439,4 +2419,10 @@ mod sealed {
         #[doc(hidden)]
         fn flags(&self) -> Vec<String>;
     }
+
+    //How an output is parsed from what `op` printed.
+    pub trait FromOutput: Sized {
+        #[doc(hidden)]
+        fn from_output(stdout: &[u8]) -> crate::Result<Self>;
+    }
 }
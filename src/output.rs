@@ -1,25 +1,49 @@
+use crate::item::Category;
+use crate::sealed::FromOutput;
+use crate::secret::{Secret, REDACTED};
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 pub use serde_json::Value;
+use std::fmt;
 
 pub type ListDocuments = Vec<ListDocument>;
 
 pub type ListItems = Vec<ListItem>;
 
 pub type ListUsers = Vec<ListUser>;
-#[derive(Serialize, Deserialize, Debug)]
+
+pub type ListVaults = Vec<Vault>;
+
+pub type ListGroups = Vec<Group>;
+
+pub type ListEvents = Vec<Event>;
+
+pub type ListTemplates = Vec<Template>;
+
+///a one time password.
+pub type Totp = Secret<String>;
+
+///the raw content of a document, which may be binary.
+pub type Document = Vec<u8>;
+
+#[cfg_attr(feature = "serialize-secrets", derive(Serialize))]
+#[derive(Deserialize, Debug)]
 pub struct ItemLite {
     pub website: String,
     pub username: String,
-    pub password: String,
+    pub password: Secret<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+///The details hold the values of the item's fields, they are left out of
+///its `Debug`.
+#[derive(Serialize, Deserialize)]
 pub struct GetItem {
     pub uuid: String,
     #[serde(alias = "templateUuid")]
     pub template_uuid: String,
-    pub trashed: String,
+    ///whether the item is in the trash.
+    #[serde(with = "yes_no")]
+    pub trashed: bool,
     #[serde(alias = "createdAt")]
     #[serde(with = "date_format")]
     pub create_at: DateTime<Local>,
@@ -33,6 +57,115 @@ pub struct GetItem {
     #[serde(alias = "vaultUuid")]
     pub vault_uuid: String,
     pub details: Value, // this field is a serde_json::Value because its content would change depend on the queried item.
+    #[serde(default)]
+    pub overview: Value,
+}
+
+impl fmt::Debug for GetItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GetItem")
+            .field("uuid", &self.uuid)
+            .field("template_uuid", &self.template_uuid)
+            .field("trashed", &self.trashed)
+            .field("create_at", &self.create_at)
+            .field("update_at", &self.update_at)
+            .field("changer_uuid", &self.changer_uuid)
+            .field("item_version", &self.item_version)
+            .field("vault_uuid", &self.vault_uuid)
+            .field("details", &format_args!("{}", REDACTED))
+            .field("overview", &self.overview)
+            .finish()
+    }
+}
+
+impl GetItem {
+    ///the item's category, None for templates this crate does not know.
+    pub fn category(&self) -> Option<Category> {
+        Category::from_template_uuid(&self.template_uuid)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Template {
+    pub uuid: String,
+    pub name: String,
+}
+
+impl Template {
+    pub fn category(&self) -> Option<Category> {
+        Category::from_template_uuid(&self.uuid)
+    }
+}
+
+///The details an item of a template starts with. Fill in the values and
+///pass it to `CreateItemCmd::template`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GetTemplate {
+    #[serde(default, rename = "notesPlain")]
+    pub notes_plain: String,
+    #[serde(default)]
+    pub fields: Vec<TemplateField>,
+    #[serde(default)]
+    pub sections: Vec<TemplateSection>,
+}
+
+///A built-in field of a template, e.g. the username of a Login. Its value
+///is left out of its `Debug`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TemplateField {
+    #[serde(default)]
+    pub designation: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+impl fmt::Debug for TemplateField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TemplateField")
+            .field("designation", &self.designation)
+            .field("name", &self.name)
+            .field("type_", &self.type_)
+            .field("value", &format_args!("{}", REDACTED))
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateSection {
+    pub name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub fields: Vec<TemplateSectionField>,
+}
+
+///A field of a template section. The short names are the ones `op` uses,
+///the value is left out of its `Debug`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TemplateSectionField {
+    ///the field type, e.g. `string` or `concealed`.
+    pub k: String,
+    ///the field name.
+    pub n: String,
+    ///the field title.
+    pub t: String,
+    ///the value, empty in a template.
+    #[serde(default)]
+    pub v: Value,
+}
+
+impl fmt::Debug for TemplateSectionField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TemplateSectionField")
+            .field("k", &self.k)
+            .field("n", &self.n)
+            .field("t", &self.t)
+            .field("v", &format_args!("{}", REDACTED))
+            .finish()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -68,6 +201,19 @@ pub struct CreateDocument {
     pub vault_uuid: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateItem {
+    pub uuid: String,
+    #[serde(alias = "createdAt")]
+    #[serde(with = "date_format")]
+    pub create_at: DateTime<Local>,
+    #[serde(alias = "updatedAt")]
+    #[serde(with = "date_format")]
+    pub update_at: DateTime<Local>,
+    #[serde(alias = "vaultUuid")]
+    pub vault_uuid: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ListDocument {
     pub uuid: String,
@@ -86,6 +232,12 @@ pub struct ListDocument {
     pub overview: Value, // this field is a serde_json::Value because its content would change depend on the queried item.
 }
 
+impl ListItem {
+    pub fn category(&self) -> Option<Category> {
+        Category::from_template_uuid(&self.template_uuid)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ListUser {
     pub uuid: String,
@@ -99,6 +251,21 @@ pub struct ListUser {
     pub state: String,
     #[serde(alias = "type")]
     pub type_: String,
+    ///the user's role in the group, only set by `list().users().group(..)`.
+    #[serde(default)]
+    pub role: Option<Role>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateUser {
+    pub uuid: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub name: String,
+    ///`P` until the user accepted the invitation and was confirmed.
+    #[serde(default)]
+    pub state: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -141,7 +308,9 @@ pub struct ListItem {
     pub uuid: String,
     #[serde(alias = "templateUuid")]
     pub template_uuid: String,
-    pub trashed: String,
+    ///whether the item is in the trash.
+    #[serde(with = "yes_no")]
+    pub trashed: bool,
     #[serde(alias = "createdAt")]
     #[serde(with = "date_format")]
     pub create_at: DateTime<Local>,
@@ -157,23 +326,549 @@ pub struct ListItem {
     pub overview: Value, // this field is a serde_json::Value because its content would change depend on the queried item.
 }
 
+///`list vaults` only prints the uuid and name of each vault,
+///the other fields are filled by `get vault` and `create vault`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Vault {
+    pub uuid: String,
+    pub name: String,
+    #[serde(default, alias = "desc")]
+    pub description: String,
+    #[serde(default, alias = "type")]
+    pub type_: String,
+    #[serde(default)]
+    pub avatar: String,
+    #[serde(default, alias = "attrVersion")]
+    pub attr_version: usize,
+    #[serde(default, alias = "contentVersion")]
+    pub content_version: usize,
+    ///the number of items in the vault.
+    #[serde(default)]
+    pub items: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Group {
+    pub uuid: String,
+    pub name: String,
+    #[serde(default, alias = "desc")]
+    pub description: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default, alias = "type")]
+    pub type_: String,
+    ///the user's role in the group, only set by `list().groups().user(..)`.
+    #[serde(default)]
+    pub role: Option<Role>,
+}
+
+///The role of a user in a group.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    #[serde(rename = "MEMBER", alias = "member")]
+    Member,
+    #[serde(rename = "MANAGER", alias = "manager")]
+    Manager,
+}
+
+impl Role {
+    ///the value of `op`'s `--role` option.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Manager => "manager",
+        }
+    }
+}
+
+///An entry of the account's activity log.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct DeleteItem {
-    pub field: String,
+pub struct Event {
+    ///the event id, which grows with time.
+    pub eid: u64,
+    #[serde(with = "date_format")]
+    pub time: DateTime<Local>,
+    #[serde(alias = "actorUuid")]
+    pub actor_uuid: String,
+    ///what happened, e.g. `create`, `delete`, `join` or `grant`.
+    pub action: String,
+    ///what it happened to, e.g. `vault`, `item` or `gm` for a group membership.
+    #[serde(alias = "objectType")]
+    pub object_type: String,
+    #[serde(alias = "objectUuid")]
+    pub object_uuid: String,
+    #[serde(default, alias = "auxInfo")]
+    pub aux_info: String,
+    #[serde(default, alias = "auxId")]
+    pub aux_id: Option<i64>,
+    #[serde(default, alias = "auxUuid")]
+    pub aux_uuid: Option<String>,
+    #[serde(default, alias = "sessionUuid")]
+    pub session_uuid: Option<String>,
+}
+
+///A permission on the items of a vault. Business accounts grant the fine
+///grained ones, Teams accounts `AllowViewing`, `AllowEditing` and `AllowManaging`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ViewItems,
+    CreateItems,
+    EditItems,
+    ArchiveItems,
+    DeleteItems,
+    ViewAndCopyPasswords,
+    ViewItemHistory,
+    ImportItems,
+    ExportItems,
+    CopyAndShareItems,
+    PrintItems,
+    ManageVault,
+    AllowViewing,
+    AllowEditing,
+    AllowManaging,
+}
+
+impl Permission {
+    ///the name `op` knows the permission by, e.g. `view_items`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ViewItems => "view_items",
+            Self::CreateItems => "create_items",
+            Self::EditItems => "edit_items",
+            Self::ArchiveItems => "archive_items",
+            Self::DeleteItems => "delete_items",
+            Self::ViewAndCopyPasswords => "view_and_copy_passwords",
+            Self::ViewItemHistory => "view_item_history",
+            Self::ImportItems => "import_items",
+            Self::ExportItems => "export_items",
+            Self::CopyAndShareItems => "copy_and_share_items",
+            Self::PrintItems => "print_items",
+            Self::ManageVault => "manage_vault",
+            Self::AllowViewing => "allow_viewing",
+            Self::AllowEditing => "allow_editing",
+            Self::AllowManaging => "allow_managing",
+        }
+    }
+}
+
+///A group or user who can access a vault, with what it is allowed to do.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VaultGrant {
+    #[serde(alias = "id")]
+    pub uuid: String,
+    pub name: String,
+    ///only set for users.
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
 }
 
+///Who can access a vault, returned by `list().vault_permissions(..)`.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct DeleteDocument {
-    pub field: String,
+pub struct VaultPermissions {
+    pub groups: Vec<VaultGrant>,
+    pub users: Vec<VaultGrant>,
+}
+
+///returned by the delete commands, which print nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deleted;
+
+///returned by the add commands, which print nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Added;
+
+///returned by the remove commands, which print nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Removed;
+
+///returned by `restore`, which prints nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Restored;
+
+///returned by `confirm`, which prints nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Confirmed;
+
+///returned by `suspend`, which prints nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suspended;
+
+///returned by `reactivate`, which prints nothing on success.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reactivated;
+
+//`op get totp` prints the bare code, it is not parsed as JSON
+//so a leading zero is kept.
+impl FromOutput for Totp {
+    fn from_output(stdout: &[u8]) -> crate::Result<Self> {
+        Ok(Secret::new(
+            String::from_utf8_lossy(stdout).trim().to_string(),
+        ))
+    }
+}
+
+impl FromOutput for Document {
+    fn from_output(stdout: &[u8]) -> crate::Result<Self> {
+        Ok(stdout.to_vec())
+    }
+}
+
+//this macro implements FromOutput for the outputs of commands
+//which print nothing on success.
+macro_rules! from_empty_output {
+    ($($Output:ident),+ $(,)?) => {
+        $(
+            impl FromOutput for $Output {
+                fn from_output(_stdout: &[u8]) -> crate::Result<Self> {
+                    Ok($Output)
+                }
+            }
+        )+
+    };
+}
+
+from_empty_output!(
+    Deleted,
+    Added,
+    Removed,
+    Restored,
+    Confirmed,
+    Suspended,
+    Reactivated
+);
+
+//this macro implements FromOutput for the outputs printed as JSON.
+macro_rules! from_json_output {
+    ($($Output:ty),+ $(,)?) => {
+        $(
+            impl FromOutput for $Output {
+                fn from_output(stdout: &[u8]) -> crate::Result<Self> {
+                    Ok(serde_json::from_slice(stdout)?)
+                }
+            }
+        )+
+    };
+}
+
+//`op` 2 prints json of another shape for these outputs,
+//it is reshaped to what `op` 1 prints before it is deserialized.
+macro_rules! from_v1_or_v2_output {
+    ($(($Output:ty, $reshape:path)),+ $(,)?) => {
+        $(
+            impl FromOutput for $Output {
+                fn from_output(stdout: &[u8]) -> crate::Result<Self> {
+                    let value: Value = serde_json::from_slice(stdout)?;
+                    Ok(serde_json::from_value($reshape(value))?)
+                }
+            }
+        )+
+    };
+}
+
+from_json_output!(Value, CreateDocument, ListEvents, ListTemplates,);
+
+from_v1_or_v2_output!(
+    (ItemLite, v2::item_lite),
+    (GetItem, v2::item),
+    (CreateItem, v2::item),
+    (ListItems, v2::items),
+    (ListDocuments, v2::items),
+    (Account, v2::account),
+    (GetUser, v2::user),
+    (ListUsers, v2::users),
+    (CreateUser, v2::renamed),
+    (Vault, v2::renamed),
+    (ListVaults, v2::all_renamed),
+    (Group, v2::renamed),
+    (ListGroups, v2::all_renamed),
+    (GetTemplate, v2::template),
+);
+
+//Reshapes the json of `op` 2 to the one of `op` 1, which is what the
+//outputs are declared for. v1 json is returned as it is.
+mod v2 {
+    use crate::item::Category;
+    use serde_json::{json, Value};
+
+    //v2 objects are identified by `id`, v1 ones by `uuid`.
+    fn is_v2(value: &Value) -> bool {
+        value.get("id").is_some() && value.get("uuid").is_none()
+    }
+
+    fn each(value: Value, reshape: fn(Value) -> Value) -> Value {
+        match value {
+            Value::Array(values) => Value::Array(values.into_iter().map(reshape).collect()),
+            value => value,
+        }
+    }
+
+    //a missing string is printed as an empty one by `op` 1.
+    fn string(value: &Value) -> Value {
+        match value {
+            Value::Null => json!(""),
+            value => value.clone(),
+        }
+    }
+
+    fn fields(value: &Value) -> Vec<Value> {
+        value["fields"].as_array().cloned().unwrap_or_default()
+    }
+
+    fn field_value(value: &Value, purpose: &str) -> Value {
+        string(
+            &fields(value)
+                .into_iter()
+                .find(|field| field["purpose"] == purpose)
+                .unwrap_or_default()["value"],
+        )
+    }
+
+    fn primary_url(value: &Value) -> Value {
+        let urls = value["urls"].as_array().cloned().unwrap_or_default();
+        urls.iter()
+            .find(|url| url["primary"] == true)
+            .or_else(|| urls.first())
+            .map(|url| string(&url["href"]))
+            .unwrap_or_else(|| json!(""))
+    }
+
+    //the `details` of an item, from its `fields` and `sections`.
+    fn details(value: &Value) -> Value {
+        let (sectioned, built_in): (Vec<Value>, Vec<Value>) = fields(value)
+            .into_iter()
+            .partition(|field| field["section"]["id"].is_string());
+        let sections: Vec<Value> = value["sections"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .map(|section| {
+                let fields: Vec<Value> = sectioned
+                    .iter()
+                    .filter(|field| field["section"]["id"] == section["id"])
+                    .map(|field| {
+                        json!({
+                            "k": string(&field["type"]).as_str().unwrap_or_default().to_lowercase(),
+                            "n": string(&field["id"]),
+                            "t": string(&field["label"]),
+                            "v": string(&field["value"]),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": string(&section["id"]),
+                    "title": string(&section["label"]),
+                    "fields": fields,
+                })
+            })
+            .collect();
+        let fields: Vec<Value> = built_in
+            .iter()
+            .filter(|field| field["purpose"] != "NOTES")
+            .map(|field| {
+                let purpose = string(&field["purpose"]);
+                json!({
+                    "designation": purpose.as_str().unwrap_or_default().to_lowercase(),
+                    "name": string(&field["id"]),
+                    "type": if field["type"] == "CONCEALED" { "P" } else { "T" },
+                    "value": string(&field["value"]),
+                })
+            })
+            .collect();
+        json!({
+            "notesPlain": field_value(value, "NOTES"),
+            "fields": fields,
+            "sections": sections,
+        })
+    }
+
+    pub fn items(value: Value) -> Value {
+        each(value, item)
+    }
+
+    pub fn item(value: Value) -> Value {
+        if !is_v2(&value) {
+            return value;
+        }
+        let template_uuid = value["category"]
+            .as_str()
+            .and_then(Category::from_v2_name)
+            .map(|category| category.template_uuid())
+            .unwrap_or_default();
+        let urls: Vec<Value> = value["urls"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .map(|url| json!({"l": string(&url["label"]), "u": string(&url["href"])}))
+            .collect();
+        json!({
+            "uuid": value["id"],
+            "templateUuid": template_uuid,
+            "trashed": if value["state"] == "DELETED" { "Y" } else { "N" },
+            "createdAt": value["created_at"],
+            "updatedAt": value["updated_at"],
+            "changerUuid": string(&value["last_edited_by"]),
+            "itemVersion": value["version"].as_u64().unwrap_or_default(),
+            "vaultUuid": string(&value["vault"]["id"]),
+            "details": details(&value),
+            "overview": {
+                "title": string(&value["title"]),
+                "url": primary_url(&value),
+                "URLs": urls,
+                "tags": value["tags"].as_array().cloned().unwrap_or_default(),
+            },
+        })
+    }
+
+    //`op` 2 prints the whole item instead of the requested fields.
+    pub fn item_lite(value: Value) -> Value {
+        if !is_v2(&value) {
+            return value;
+        }
+        json!({
+            "website": primary_url(&value),
+            "username": field_value(&value, "USERNAME"),
+            "password": field_value(&value, "PASSWORD"),
+        })
+    }
+
+    pub fn account(value: Value) -> Value {
+        if !is_v2(&value) {
+            return value;
+        }
+        json!({
+            "uuid": value["id"],
+            "name": string(&value["name"]),
+            "type": string(&value["type"]),
+            "state": string(&value["state"]),
+            "avatar": "",
+            "domain": string(&value["domain"]),
+            "attrVersion": 0,
+            "createdAt": value["created_at"],
+            "baseAvatarURL": "",
+            "baseAttachmentURL": "",
+        })
+    }
+
+    //`op` 2 only prints the full name of a user.
+    fn names(value: &Value) -> (String, String) {
+        let name = value["name"].as_str().unwrap_or_default();
+        let (first, last) = name.split_once(' ').unwrap_or((name, ""));
+        (first.to_string(), last.to_string())
+    }
+
+    pub fn user(value: Value) -> Value {
+        if !is_v2(&value) {
+            return value;
+        }
+        let (first_name, last_name) = names(&value);
+        json!({
+            "uuid": value["id"],
+            "createdAt": value["created_at"],
+            "updatedAt": value["updated_at"],
+            "lastAuthAt": value["last_auth_at"],
+            "firstName": first_name,
+            "lastName": last_name,
+            "name": string(&value["name"]),
+            "email": string(&value["email"]),
+            "attrVersion": 0,
+            "keysetVersion": 0,
+            "language": "",
+            "accountKeyFormat": "",
+            "accountKeyUuid": "",
+            "combinedPermissions": 0,
+            "avatar": "",
+            "state": string(&value["state"]),
+            "type": string(&value["type"]),
+        })
+    }
+
+    pub fn users(value: Value) -> Value {
+        each(value, |value| {
+            if !is_v2(&value) {
+                return value;
+            }
+            let (first_name, last_name) = names(&value);
+            let mut user = renamed(value);
+            user["firstName"] = json!(first_name);
+            user["lastName"] = json!(last_name);
+            user["avatar"] = json!("");
+            user
+        })
+    }
+
+    //vaults and groups only differ by `id`.
+    pub fn renamed(mut value: Value) -> Value {
+        if !is_v2(&value) {
+            return value;
+        }
+        if let Some(object) = value.as_object_mut() {
+            if let Some(id) = object.remove("id") {
+                object.insert("uuid".to_string(), id);
+            }
+        }
+        value
+    }
+
+    pub fn all_renamed(value: Value) -> Value {
+        each(value, renamed)
+    }
+
+    //a v2 template is an empty item.
+    pub fn template(value: Value) -> Value {
+        if value.get("category").is_none() {
+            return value;
+        }
+        details(&value)
+    }
+}
+
+//`op` v1 prints flags as "Y" or "N", they are deserialized to a bool.
+mod yes_no {
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(flag: &bool, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bool(*flag)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Flag {
+            Bool(bool),
+            Str(String),
+        }
+        match Flag::deserialize(deserializer)? {
+            Flag::Bool(flag) => Ok(flag),
+            Flag::Str(flag) => match flag.as_str() {
+                "Y" => Ok(true),
+                "N" => Ok(false),
+                other => Err(serde::de::Error::custom(format!(
+                    "expected \"Y\" or \"N\", found {:?}",
+                    other
+                ))),
+            },
+        }
+    }
 }
 
 //this mod helped to deserialize json string to chrono::DateTime.
 //And it was copied from StackOverflow!
 mod date_format {
-    use chrono::{DateTime, Local, TimeZone};
+    use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
     use serde::{self, Deserialize, Deserializer, Serializer};
 
-    const FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S%.f%Z";
+    const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f%Z";
 
     pub fn serialize<S>(date: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -188,8 +883,10 @@ mod date_format {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
+        let naive = NaiveDateTime::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom)?;
         Local
-            .datetime_from_str(&s, FORMAT)
-            .map_err(serde::de::Error::custom)
+            .from_local_datetime(&naive)
+            .earliest()
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid local time: {}", s)))
     }
 }
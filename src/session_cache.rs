@@ -0,0 +1,118 @@
+//! An encrypted on-disk cache of session tokens, keyed by account shorthand,
+//! so a short-lived program can reuse a session instead of asking for the
+//! master password on every run.
+//!
+//! Tokens are sealed with ChaCha20-Poly1305 under a key the program keeps
+//! somewhere else, e.g. in the OS keyring. Needs the `session-cache` feature.
+use crate::error::Error;
+use crate::secret::Secret;
+use crate::Result;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{AeadCore, ChaCha20Poly1305, Nonce};
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+const NONCE_LEN: usize = 12;
+
+///Keeps the session token of each account in `<dir>/<shorthand>.session`.
+#[derive(Debug, Clone)]
+pub struct SessionCache {
+    dir: PathBuf,
+    key: Secret<[u8; 32]>,
+}
+
+impl SessionCache {
+    ///cache the tokens in `dir`, which is created when a token is stored.
+    pub fn new(dir: impl Into<PathBuf>, key: Secret<[u8; 32]>) -> Self {
+        Self {
+            dir: dir.into(),
+            key,
+        }
+    }
+
+    ///a random key for `new`.
+    pub fn generate_key() -> Secret<[u8; 32]> {
+        Secret::new(ChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    ///the cached token of `account`. None when there is none, or when it
+    ///can not be decrypted, e.g. it was stored with another key.
+    pub async fn load(&self, account: &str) -> Result<Option<Secret<String>>> {
+        let sealed = match tokio::fs::read(self.path(account)?).await {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if sealed.len() < NONCE_LEN {
+            return Ok(None);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: account.as_bytes(),
+        };
+        let token = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .ok()
+            .and_then(|token| String::from_utf8(token).ok());
+        Ok(token.map(Secret::new))
+    }
+
+    ///encrypt and store the token of `account`, replacing the cached one.
+    ///The file is only readable by its owner.
+    pub async fn store(&self, account: &str, token: &Secret<String>) -> Result<()> {
+        let path = self.path(account)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        //the account is authenticated too, so a file can not be swapped
+        //for the one of another account.
+        let payload = Payload {
+            msg: token.expose().trim().as_bytes(),
+            aad: account.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, payload)
+            .map_err(|_| Error::SessionError("failed to encrypt the session".to_string()))?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        //written aside and renamed, so a concurrent load never sees half a file.
+        let partial = path.with_extension("session.partial");
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&partial).await?;
+        file.write_all(&nonce).await?;
+        file.write_all(&ciphertext).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    ///forget the token of `account`.
+    pub async fn remove(&self, account: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(account)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(self.key.expose().into())
+    }
+
+    //the shorthand becomes a file name, so it may not leave `dir`.
+    fn path(&self, account: &str) -> Result<PathBuf> {
+        let valid = !account.is_empty()
+            && account
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        match valid {
+            true => Ok(self.dir.join(format!("{}.session", account))),
+            false => Err(Error::SessionError(format!(
+                "invalid account shorthand: {}",
+                account
+            ))),
+        }
+    }
+}
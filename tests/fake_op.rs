@@ -0,0 +1,610 @@
+//Runs the builders against the fake-op binary.
+//`cargo test --features fake-op`
+#![cfg(feature = "fake-op")]
+use onepassword_cli::error::ErrorKind;
+use onepassword_cli::item::{Assignment, FieldType, PasswordRecipe};
+use onepassword_cli::output::{Permission, Role};
+use onepassword_cli::{Category, OpCLI, OpCLIBuilder};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Once;
+
+const PASSWORD: &str = "fake-op";
+
+static VAULT: Once = Once::new();
+
+//a fresh vault for every run of the tests.
+fn vault() -> PathBuf {
+    let vault = std::env::temp_dir().join(format!("fake-op-vault-{}.json", std::process::id()));
+    VAULT.call_once(|| {
+        let _ = std::fs::remove_file(&vault);
+    });
+    vault
+}
+
+fn builder() -> OpCLIBuilder {
+    OpCLI::builder()
+        .bin_path(env!("CARGO_BIN_EXE_fake-op"))
+        .env("FAKE_OP_VAULT", &vault().to_string_lossy())
+}
+
+async fn op_cli() -> OpCLI {
+    builder().signin("my", PASSWORD).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_signin_wrong_password() {
+    let res = builder().signin("my", "wrong").await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::Unauthorized));
+}
+
+#[tokio::test]
+async fn test_current_dir() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/test");
+    let op_cli = builder()
+        .current_dir(dir)
+        .signin("my", PASSWORD)
+        .await
+        .unwrap();
+    let doc = op_cli.create().document("newnew_json.json").run().await;
+    assert!(doc.is_ok());
+}
+
+#[tokio::test]
+async fn test_get() {
+    let op_cli = op_cli().await;
+    assert!(op_cli.get().account().run().await.is_ok());
+    let item_lite = op_cli.get().item_lite("facebook").run().await.unwrap();
+    assert_eq!(item_lite.username, "me@example.com");
+    let item = op_cli.get().item("facebook").run().await.unwrap();
+    assert_eq!(item.template_uuid, "001");
+    assert!(op_cli.get().totp("facebook").run().await.is_ok());
+    assert!(op_cli.get().document("new_doc.txt").run().await.is_ok());
+    let res = op_cli.get().item("nothing").run().await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn test_list_and_get_user() {
+    let op_cli = op_cli().await;
+    assert!(op_cli.list().items().run().await.is_ok());
+    assert!(op_cli.list().documents().run().await.is_ok());
+    let users = op_cli.list().users().run().await.unwrap();
+    let user = op_cli.get().user(&users[0].uuid).run().await.unwrap();
+    assert_eq!(user.email, users[0].email);
+}
+
+#[tokio::test]
+async fn test_create_and_delete_document() {
+    let op_cli = op_cli().await;
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test/newnew_json.json");
+    let doc = op_cli.create().document(path).run().await.unwrap();
+    let res = op_cli.delete().document(&doc.uuid).run().await;
+    assert!(res.is_ok());
+    assert!(op_cli.get().document(&doc.uuid).run().await.is_err());
+}
+
+#[tokio::test]
+async fn test_binary_document() {
+    let op_cli = op_cli().await;
+    let content: Vec<u8> = (0..=255u8).rev().collect();
+    let dir = std::env::temp_dir().join(format!("fake-op-docs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("keystore.p12");
+    std::fs::write(&source, &content).unwrap();
+    let doc = op_cli
+        .create()
+        .document(&source.to_string_lossy())
+        .run()
+        .await
+        .unwrap();
+
+    let downloaded = op_cli.get().document(&doc.uuid).run().await.unwrap();
+    assert_eq!(downloaded, content);
+
+    let mut streamed: Vec<u8> = Vec::new();
+    let res = op_cli
+        .get()
+        .document(&doc.uuid)
+        .stream_to(&mut streamed)
+        .await;
+    assert!(res.is_ok());
+    assert_eq!(streamed, content);
+
+    let saved = dir.join("saved.p12");
+    let res = op_cli.get().document(&doc.uuid).save_to(&saved).await;
+    assert!(res.is_ok());
+    assert_eq!(std::fs::read(&saved).unwrap(), content);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_create_item() {
+    let op_cli = op_cli().await;
+    let created = op_cli
+        .create()
+        .item(Category::Login)
+        .title("github")
+        .url("https://github.com")
+        .username("octocat")
+        .password("hunter2")
+        .run()
+        .await
+        .unwrap();
+    let item = op_cli.get().item_lite(&created.uuid).run().await.unwrap();
+    assert_eq!(item.website, "https://github.com");
+    assert_eq!(item.username, "octocat");
+    assert_eq!(item.password.expose(), "hunter2");
+}
+
+#[tokio::test]
+async fn test_edit_item() {
+    let op_cli = op_cli().await;
+    let created = op_cli
+        .create()
+        .item(Category::Login)
+        .title("gitlab")
+        .username("octocat")
+        .password("hunter2")
+        .run()
+        .await
+        .unwrap();
+    let item = op_cli
+        .edit()
+        .item(&created.uuid)
+        .assign(Assignment::new("username", "hubot"))
+        .assign(Assignment::in_section(
+            "Recovery",
+            "code",
+            FieldType::Concealed,
+            "abcd-efgh",
+        ))
+        .generate_password(PasswordRecipe {
+            length: Some(40),
+            ..PasswordRecipe::default()
+        })
+        .url("https://gitlab.com")
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(item.item_version, 2);
+    assert_eq!(item.details["sections"][0]["fields"][0]["v"], "abcd-efgh");
+    let lite = op_cli.get().item_lite(&created.uuid).run().await.unwrap();
+    assert_eq!(lite.username, "hubot");
+    assert_eq!(lite.website, "https://gitlab.com");
+    assert_eq!(lite.password.expose().len(), 40);
+}
+
+#[tokio::test]
+async fn test_vaults() {
+    let op_cli = op_cli().await;
+    let name = format!("project-{}", std::process::id());
+    let created = op_cli
+        .create()
+        .vault(&name)
+        .description("per-project secrets")
+        .allow_admins_to_manage(true)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(created.name, name);
+    let vaults = op_cli.list().vaults().run().await.unwrap();
+    assert!(vaults.iter().any(|vault| vault.uuid == created.uuid));
+
+    let item = op_cli
+        .create()
+        .item(Category::SecureNote)
+        .title("notes")
+        .run()
+        .await
+        .unwrap();
+    let moved = op_cli
+        .edit()
+        .item(&item.uuid)
+        .copy_to_vault_and_trash_original(&name)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(moved.vault_uuid, created.uuid);
+    let vault = op_cli.get().vault(&created.uuid).run().await.unwrap();
+    assert_eq!(vault.items, Some(1));
+
+    let renamed = format!("{}-renamed", name);
+    let edited = op_cli
+        .edit()
+        .vault(&created.uuid)
+        .name(&renamed)
+        .description("renamed")
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(edited.uuid, created.uuid);
+    assert_eq!(edited.description, "renamed");
+
+    assert!(op_cli.delete().vault(&renamed).run().await.is_ok());
+    let res = op_cli.get().vault(&renamed).run().await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn test_groups() {
+    let op_cli = op_cli().await;
+    let name = format!("developers-{}", std::process::id());
+    let created = op_cli
+        .create()
+        .group(&name)
+        .description("our developers")
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(created.description, "our developers");
+    let admins = op_cli.list().groups().vault("Private").run().await.unwrap();
+    assert!(admins.iter().any(|group| group.name == "Administrators"));
+
+    op_cli
+        .add()
+        .user_to_group("wendy@example.com", &created.uuid)
+        .role(Role::Manager)
+        .run()
+        .await
+        .unwrap();
+    let groups = op_cli
+        .list()
+        .groups()
+        .user("wendy@example.com")
+        .run()
+        .await
+        .unwrap();
+    let group = groups.iter().find(|group| group.uuid == created.uuid);
+    assert_eq!(group.and_then(|group| group.role), Some(Role::Manager));
+    op_cli
+        .remove()
+        .user_from_group("wendy@example.com", &created.uuid)
+        .run()
+        .await
+        .unwrap();
+
+    let renamed = format!("{}-renamed", name);
+    let edited = op_cli
+        .edit()
+        .group(&created.uuid)
+        .name(&renamed)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(edited.name, renamed);
+    assert!(op_cli.delete().group(&renamed).run().await.is_ok());
+    let res = op_cli.get().group(&created.uuid).run().await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn test_user_lifecycle() {
+    let op_cli = op_cli().await;
+    let email = format!("alex-{}@example.com", std::process::id());
+    let created = op_cli
+        .create()
+        .user(&email, "Alex Doe")
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(created.state, "P");
+    op_cli.confirm().user(&email).run().await.unwrap();
+    assert_eq!(op_cli.get().user(&email).run().await.unwrap().state, "A");
+
+    op_cli
+        .suspend()
+        .user(&email)
+        .deauthorize_devices_after(std::time::Duration::from_secs(300))
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(op_cli.get().user(&email).run().await.unwrap().state, "S");
+    op_cli.reactivate().user(&email).run().await.unwrap();
+
+    let edited = op_cli
+        .edit()
+        .user(&email)
+        .name("Alex Roe")
+        .travel_mode(true)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(edited.name, "Alex Roe");
+    assert_eq!(edited.state, "A");
+
+    op_cli
+        .add()
+        .user_to_group(&email, "Administrators")
+        .run()
+        .await
+        .unwrap();
+    let members = op_cli
+        .list()
+        .users()
+        .group("Administrators")
+        .run()
+        .await
+        .unwrap();
+    let member = members.iter().find(|user| user.email == email).unwrap();
+    assert_eq!(member.role, Some(Role::Member));
+    let with_access = op_cli.list().users().vault("Private").run().await.unwrap();
+    assert!(with_access.iter().any(|user| user.email == email));
+
+    op_cli.delete().user(&email).run().await.unwrap();
+    let res = op_cli.get().user(&email).run().await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn test_vault_permissions() {
+    let op_cli = op_cli().await;
+    let vault = format!("shared-{}", std::process::id());
+    let group = format!("readers-{}", std::process::id());
+    op_cli.create().vault(&vault).run().await.unwrap();
+    op_cli.create().group(&group).run().await.unwrap();
+
+    op_cli
+        .add()
+        .group_to_vault(&group, &vault)
+        .permissions(&[Permission::ViewItems, Permission::ViewAndCopyPasswords])
+        .run()
+        .await
+        .unwrap();
+    op_cli
+        .add()
+        .user_to_vault("wendy@example.com", &vault)
+        .permissions(&[Permission::ManageVault])
+        .run()
+        .await
+        .unwrap();
+    let permissions = op_cli.list().vault_permissions(&vault).run().await.unwrap();
+    assert_eq!(permissions.groups.len(), 1);
+    assert_eq!(
+        permissions.groups[0].permissions,
+        [Permission::ViewItems, Permission::ViewAndCopyPasswords]
+    );
+    assert_eq!(permissions.users[0].permissions, [Permission::ManageVault]);
+
+    op_cli
+        .remove()
+        .group_from_vault(&group, &vault)
+        .permissions(&[Permission::ViewAndCopyPasswords])
+        .run()
+        .await
+        .unwrap();
+    op_cli
+        .remove()
+        .user_from_vault("wendy@example.com", &vault)
+        .run()
+        .await
+        .unwrap();
+    let permissions = op_cli.list().vault_permissions(&vault).run().await.unwrap();
+    assert_eq!(permissions.groups[0].permissions, [Permission::ViewItems]);
+    assert!(permissions.users.is_empty());
+}
+
+#[tokio::test]
+async fn test_stream_events() {
+    use futures::TryStreamExt;
+
+    let op_cli = builder()
+        .env("FAKE_OP_EVENT_PAGE_SIZE", "2")
+        .signin("my", PASSWORD)
+        .await
+        .unwrap();
+    let vault = format!("audited-{}", std::process::id());
+    op_cli.create().vault(&vault).run().await.unwrap();
+    let page = op_cli.list().events().run().await.unwrap();
+    assert_eq!(page.len(), 2);
+
+    let events: Vec<_> = op_cli.list().events().stream().try_collect().await.unwrap();
+    assert!(events.len() >= 4);
+    assert!(events.windows(2).all(|pair| pair[0].eid > pair[1].eid));
+    assert!(events
+        .iter()
+        .any(|event| event.action == "create" && event.object_uuid == vault));
+}
+
+#[tokio::test]
+async fn test_templates() {
+    let op_cli = op_cli().await;
+    let templates = op_cli.list().templates().run().await.unwrap();
+    assert!(templates
+        .iter()
+        .all(|template| template.category().is_some()));
+
+    let mut template = op_cli.get().template("001").run().await.unwrap();
+    for field in template.fields.iter_mut() {
+        if field.designation == "username" {
+            field.value = "octocat".to_string();
+        }
+    }
+    let created = op_cli
+        .create()
+        .item(Category::Login)
+        .template(template)
+        .title("from template")
+        .url("https://github.com")
+        .run()
+        .await
+        .unwrap();
+    let item = op_cli.get().item(&created.uuid).run().await.unwrap();
+    assert_eq!(item.category(), Some(Category::Login));
+    let lite = op_cli.get().item_lite(&created.uuid).run().await.unwrap();
+    assert_eq!(lite.username, "octocat");
+}
+
+#[tokio::test]
+async fn test_trash_and_archive() {
+    let op_cli = op_cli().await;
+    let name = format!("trash-{}", std::process::id());
+    op_cli.create().vault(&name).run().await.unwrap();
+    let trashed = op_cli
+        .create()
+        .item(Category::SecureNote)
+        .title("old notes")
+        .vault(&name)
+        .run()
+        .await
+        .unwrap();
+    let archived = op_cli
+        .create()
+        .item(Category::SecureNote)
+        .title("older notes")
+        .vault(&name)
+        .run()
+        .await
+        .unwrap();
+    op_cli.delete().item(&trashed.uuid).run().await.unwrap();
+    op_cli
+        .delete()
+        .item(&archived.uuid)
+        .archive()
+        .run()
+        .await
+        .unwrap();
+
+    let listed = |items: Vec<onepassword_cli::output::ListItem>| -> Vec<String> {
+        items
+            .into_iter()
+            .filter(|item| item.vault_uuid == trashed.vault_uuid)
+            .map(|item| item.uuid)
+            .collect()
+    };
+    let items = op_cli.list().items().run().await.unwrap();
+    assert!(listed(items).is_empty());
+    let items = op_cli.list().items().include_trash().run().await.unwrap();
+    assert_eq!(listed(items), [trashed.uuid.as_str()]);
+    let items = op_cli.list().items().include_archive().run().await.unwrap();
+    assert_eq!(listed(items), [archived.uuid.as_str()]);
+    let item = op_cli.get().item(&trashed.uuid).run().await.unwrap();
+    assert!(item.trashed);
+
+    op_cli.restore().item(&archived.uuid).run().await.unwrap();
+    op_cli.delete().trash(&name).run().await.unwrap();
+    let items = op_cli
+        .list()
+        .items()
+        .include_trash()
+        .include_archive()
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(listed(items), [archived.uuid.as_str()]);
+    let res = op_cli.get().item(&trashed.uuid).run().await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn test_edit_document() {
+    let op_cli = op_cli().await;
+    let dir = std::env::temp_dir().join(format!("fake-op-edit-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("tls.pem");
+    std::fs::write(&source, "first").unwrap();
+    let doc = op_cli
+        .create()
+        .document(&source.to_string_lossy())
+        .run()
+        .await
+        .unwrap();
+
+    std::fs::write(&source, "second").unwrap();
+    let item = op_cli
+        .edit()
+        .document(&doc.uuid)
+        .file(&source)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(item.uuid, doc.uuid);
+    assert_eq!(item.item_version, 2);
+    let downloaded = op_cli.get().document(&doc.uuid).run().await.unwrap();
+    assert_eq!(downloaded, b"second");
+
+    let content: Vec<u8> = (0..=255u8).collect();
+    let item = op_cli
+        .edit()
+        .document(&doc.uuid)
+        .bytes(content.clone())
+        .file_name("bundle.pem")
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(item.item_version, 3);
+    assert_eq!(item.details["documentAttributes"]["fileName"], "bundle.pem");
+    let downloaded = op_cli.get().document(&doc.uuid).run().await.unwrap();
+    assert_eq!(downloaded, content);
+
+    let title = format!("tls bundle {}", std::process::id());
+    let file = tokio::fs::File::open(&source).await.unwrap();
+    let item = op_cli
+        .edit()
+        .document(&doc.uuid)
+        .reader(file)
+        .title(&title)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(item.uuid, doc.uuid);
+    assert_eq!(item.item_version, 4);
+    let downloaded = op_cli.get().document(&title).run().await.unwrap();
+    assert_eq!(downloaded, b"second");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_reuse_session() {
+    //sign in the way a user would, with `op signin my --raw`.
+    let mut signin = Command::new(env!("CARGO_BIN_EXE_fake-op"))
+        .args(["signin", "my", "--raw"])
+        .env("FAKE_OP_VAULT", vault())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    signin
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(PASSWORD.as_bytes())
+        .unwrap();
+    let token = String::from_utf8(signin.wait_with_output().unwrap().stdout).unwrap();
+
+    let op_cli = builder().session("my", token.trim()).await.unwrap();
+    assert!(op_cli.list().vaults().run().await.is_ok());
+    let res = builder().session("my", "not-a-session").await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::SessionExpired));
+}
+
+#[tokio::test]
+async fn test_add_account() {
+    //a vault of its own, the account is added under another shorthand.
+    let vault = std::env::temp_dir().join(format!("fake-op-account-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&vault);
+    let builder = || {
+        OpCLI::builder()
+            .bin_path(env!("CARGO_BIN_EXE_fake-op"))
+            .env("FAKE_OP_VAULT", &vault.to_string_lossy())
+    };
+    let res = builder()
+        .add_account("my.1password.com", "wendy@example.com", "A3-WRONG")
+        .signin(PASSWORD)
+        .await;
+    assert!(matches!(res, Err(e) if e.kind() == ErrorKind::Unauthorized));
+    let op_cli = builder()
+        .add_account(
+            "my.1password.com",
+            "wendy@example.com",
+            "A3-FAKEOP-ABCDEF-GHIJK-LMNOP-QRSTU-VWXYZ",
+        )
+        .shorthand("work")
+        .signin(PASSWORD)
+        .await
+        .unwrap();
+    assert!(op_cli.get().account().run().await.is_ok());
+    assert!(builder().signin("work", PASSWORD).await.is_ok());
+    let _ = std::fs::remove_file(&vault);
+}